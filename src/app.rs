@@ -1,14 +1,15 @@
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
 
 use anyhow::Result;
-use crossterm::event::{self, Event};
+use clap::ValueEnum;
 use lilicore::{
-    auth::{auth_introspect_token, KeycloakDecodedAccessToken},
     code_analyst::{self, project_files::get_project_files},
-    code_missions_api::{
-        rate_limit_get, set_fail, MissionAction, MissionActionType, SetFailRequest,
-    },
-    configjson, git_repo,
+    code_missions_api::SetFailRequest,
+    git_repo,
     io::LocalPath,
     rate_limit::RateLimitMe,
 };
@@ -17,19 +18,33 @@ use ratatui::{
     Frame,
 };
 use strum::Display;
+use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
+    backend::{get_setting, MissionBackend},
     components::{header::HeaderStatus, text_input::TextInputComponent},
-    redraw_app,
-    shortcuts::{handle_global_shortcuts, ShortcutHandlerResponse},
-    utils::{list::SelectableList, llm_tokens::llm_tokens_count_string},
+    config,
+    conflicts::{FileConflict, ProjectSnapshot},
+    events::AppEvent,
+    history::{append_history_entry, load_history, HistoryEntry, MissionOutcome},
+    missions::{ActionItem, ActionRevision, Execution, MissionTask, ProposedAction, Refinement},
+    presets::{load_presets, ContextPreset},
+    reduction::ContextReduction,
+    shortcuts::{handle_global_shortcuts, handle_mission_shortcuts, ShortcutHandlerResponse},
+    utils::{
+        diff::action_proposed_content, editor::edit_in_external_editor, list::SelectableList,
+        llm_tokens::TokenCounter,
+    },
+    verification::Verification,
     views::{
-        AddContextFilesView, AppView, CommitTempBranchView, CreateTempBranchView, MissionView,
-        SearchableListType, SignInView,
+        handle_context_command_event, handle_mission_event, handle_verification_event,
+        project_picker_items, AddContextFilesView, AppView, CommitTempBranchView,
+        CreateTempBranchView, HistoryView, MissionView, PresetsView, SearchableListType,
+        SignInView,
     },
 };
 
-#[derive(Debug, PartialEq, Default, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Default, Clone, Eq, Hash, ValueEnum)]
 pub enum AppScreen {
     #[default]
     Mission,
@@ -37,6 +52,8 @@ pub enum AppScreen {
     CreateTempBranch,
     CommitTempBranch,
     AddContextFiles,
+    History,
+    Presets,
 }
 
 #[derive(Debug, PartialEq, Default, Clone, Display)]
@@ -51,6 +68,32 @@ pub enum FocusedBlock {
     SignInButton,
     CommitMessage,
     SearchContextFileInput,
+    ActionHunks,
+    SearchHistoryInput,
+    Feedback,
+    Conflicts,
+    Verification,
+    ContextReduction,
+    ContextRanges,
+    ContextCommandInput,
+    PresetNameInput,
+    Presets,
+}
+
+#[derive(Debug, PartialEq, Default, Clone)]
+pub enum ActionPreviewMode {
+    #[default]
+    Diff,
+    Content,
+    SideBySide,
+}
+
+/// Startup options given on the command line.
+#[derive(Debug, Default)]
+pub struct StartOptions {
+    pub no_temp_branch: bool,
+    pub base_branch: Option<String>,
+    pub screen: Option<AppScreen>,
 }
 
 #[derive(Debug, Clone)]
@@ -61,18 +104,63 @@ pub struct AppState {
     pub signed_in: bool,
     pub input_values: HashMap<String, String>,
     pub context_items: SelectableList<(String, String)>,
-    pub action_items: SelectableList<MissionAction>,
+    pub action_items: SelectableList<ActionItem>,
     pub searchable_list: SelectableList<(String, String)>,
     pub header_status: HeaderStatus,
     pub user_name: String,
     pub execution_id: Option<String>,
     pub searchable_list_type: SearchableListType,
     pub rate_limit: Option<RateLimitMe>,
+    pub backend: Arc<dyn MissionBackend>,
+    pub event_sender: UnboundedSender<AppEvent>,
+    pub mission_task: Option<MissionTask>,
+    // command items waiting for their output
+    pub pending_context_commands: HashSet<String>,
+    // a mission started while commands were running, `generate_context` and the refinement,
+    // sent once their output is in the context
+    pub deferred_mission: Option<(bool, Option<Refinement>)>,
+    pub action_preview_mode: ActionPreviewMode,
+    // shared by both sides of the side-by-side diff so they scroll together
+    pub preview_scroll: u16,
+    pub selected_hunk: usize,
+    // line under the cursor when picking line ranges of a context file, 1-based
+    pub range_cursor: usize,
+    // first line of the range being picked
+    pub range_anchor: Option<usize>,
+    // branch given with --base-branch, used instead of the current one when creating the temp branch
+    pub requested_base_branch: Option<String>,
+    // set by the Actions block, the main loop hands the terminal to the editor
+    pub edit_action_requested: bool,
+    // past missions of the project, newest first
+    pub history_entries: SelectableList<HistoryEntry>,
+    // saved context sets of the project, listed in the Presets screen
+    pub presets: SelectableList<ContextPreset>,
+    // every set of actions proposed since the mission was sent, oldest first
+    pub action_revisions: Vec<ActionRevision>,
+    pub current_revision: usize,
+    // files changed on disk since the actions were proposed, shown when approving
+    pub file_conflicts: SelectableList<FileConflict>,
+    // verify commands running or finished on the last applied mission
+    pub verification: Option<Verification>,
+    // a mission over the token budget, waiting for its reduced context to be confirmed
+    pub context_reduction: Option<ContextReduction>,
+    pub token_counter: TokenCounter,
+    // tokens of the project file list sent with every mission, counted once
+    pub project_files_tokens: Option<usize>,
+    // tokens of the context items with the hash of the items they were counted for
+    pub context_tokens: Option<(u64, usize)>,
+    // ticks of the event loop, they animate the spinner
+    pub ticks: usize,
     // pub base_branch_name: String,
 }
 
 impl AppState {
-    pub async fn new(project_dir: String) -> Result<Self> {
+    pub async fn new(
+        project_dir: String,
+        backend: Arc<dyn MissionBackend>,
+        event_sender: UnboundedSender<AppEvent>,
+        options: StartOptions,
+    ) -> Result<Self> {
         // let mocked_action_items: Vec<MissionAction> = vec![
         //     MissionAction {
         //         path: String::from("/test1"),
@@ -92,41 +180,20 @@ impl AppState {
         // ];
         // let base_branch_name = git_repo::get_current_branch_name(&project_dir)?;
         let current_branch_name = git_repo::get_current_branch_name(&project_dir)?;
-        let screen = if current_branch_name.clone().starts_with("temp-") {
+        let on_temp_branch = current_branch_name.clone().starts_with("temp-");
+        let screen = if on_temp_branch || options.no_temp_branch {
             AppScreen::default()
         } else {
             AppScreen::CreateTempBranch
         };
-        let mut signed_in = false;
-        let mut user_name = String::from("Guest");
-        let access_token = configjson::get("access_token");
-        if access_token.clone().is_some() {
-            let access_token = access_token.unwrap().clone();
-            // validate access token
-            let introspected = auth_introspect_token(&access_token).await;
-            match introspected {
-                Ok(introspected) => {
-                    if introspected.active {
-                        signed_in = true;
-                    }
-                }
-                Err(_err) => {
-                    // user is not signed in
-                }
-            };
-
-            // get user name from current access token
-            if signed_in.clone() {
-                let decoded = KeycloakDecodedAccessToken::new(&access_token).ok();
-                if decoded.is_some() {
-                    let decoded = decoded.unwrap();
-                    user_name = decoded.get_user_name().unwrap_or(String::from("Guest"));
-                    // signed_in = true;
-                }
-            }
-        }
-        let rate_limit = rate_limit_get().await.ok();
-        Ok(Self {
+        let (signed_in, user_name) = match backend.get_signed_in_user_name().await {
+            Some(user_name) => (true, user_name),
+            None => (false, String::from("Guest")),
+        };
+        let rate_limit = backend.rate_limit_get().await.ok();
+        let context_size = get_setting("llm_context_size").and_then(|size| size.parse().ok());
+        let token_counter = TokenCounter::new(&backend.model(), context_size);
+        let mut state = Self {
             project_dir,
             screen,
             focused_block: FocusedBlock::default(),
@@ -140,8 +207,71 @@ impl AppState {
             execution_id: None,
             searchable_list_type: SearchableListType::ProjectFiles,
             rate_limit,
+            backend,
+            event_sender,
+            mission_task: None,
+            pending_context_commands: HashSet::new(),
+            deferred_mission: None,
+            action_preview_mode: ActionPreviewMode::default(),
+            preview_scroll: 0,
+            selected_hunk: 0,
+            range_cursor: 1,
+            range_anchor: None,
+            requested_base_branch: options.base_branch.clone(),
+            edit_action_requested: false,
+            history_entries: SelectableList::new(None, vec![]),
+            presets: SelectableList::new(None, vec![]),
+            action_revisions: vec![],
+            current_revision: 0,
+            file_conflicts: SelectableList::new(None, vec![]),
+            verification: None,
+            context_reduction: None,
+            token_counter,
+            project_files_tokens: None,
+            context_tokens: None,
+            ticks: 0,
             // base_branch_name: current_branch_name,
-        })
+        };
+        if let (true, Some(base_branch)) = (on_temp_branch, &options.base_branch) {
+            state.set_base_branch_name(base_branch)?;
+        }
+        if let Some(screen) = options.screen {
+            state.open_screen(screen);
+        }
+        Ok(state)
+    }
+
+    /// Opens a screen with the same focus the shortcuts leading to it would set.
+    pub fn open_screen(&mut self, screen: AppScreen) {
+        let focused_block = match screen {
+            AppScreen::Mission | AppScreen::CreateTempBranch => FocusedBlock::Home,
+            AppScreen::SignIn => FocusedBlock::UsernameInput,
+            AppScreen::CommitTempBranch => FocusedBlock::CommitMessage,
+            AppScreen::AddContextFiles => {
+                let list = project_picker_items(self.get_project_files().unwrap_or_default());
+                self.set_searchable_list(list, SearchableListType::ProjectFiles);
+                FocusedBlock::SearchContextFileInput
+            }
+            AppScreen::History => {
+                self.history_entries =
+                    SelectableList::new(Some(0), load_history(&self.project_dir));
+                self.set_input_value(&FocusedBlock::SearchHistoryInput, "");
+                FocusedBlock::SearchHistoryInput
+            }
+            AppScreen::Presets => {
+                let presets = match load_presets(&self.project_dir) {
+                    Ok(presets) => presets,
+                    Err(err) => {
+                        self.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
+                        vec![]
+                    }
+                };
+                self.presets = SelectableList::new(Some(0), presets);
+                FocusedBlock::Presets
+            }
+        };
+        self.set_screen(screen);
+        self.set_focused_block(focused_block);
     }
 
     pub fn set_screen(&mut self, screen: AppScreen) {
@@ -159,7 +289,6 @@ impl AppState {
 
     pub fn set_header_status(&mut self, status: HeaderStatus) {
         self.header_status = status;
-        redraw_app(self);
     }
 
     pub fn get_input_value_from_focused(&self, focused_block: FocusedBlock) -> String {
@@ -192,14 +321,122 @@ impl AppState {
         self.searchable_list_type = list_type;
     }
 
-    pub fn set_action_items(&mut self, items: Vec<MissionAction>) {
-        self.action_items = SelectableList::new(None, items.clone());
+    pub fn set_action_items(&mut self, items: Vec<ProposedAction>) {
+        self.action_items =
+            SelectableList::new(None, items.iter().cloned().map(ActionItem::new).collect());
         if items.len() > 0 {
             self.set_focused_block(FocusedBlock::Actions);
             self.action_items.select(Some(0));
         }
     }
 
+    /// Returns the history entries matching the search input, keeping the selected index.
+    pub fn filtered_history_entries(&self) -> SelectableList<HistoryEntry> {
+        let query = self.get_input_value_from_focused(FocusedBlock::SearchHistoryInput);
+        self.history_entries
+            .filter_and_collect(|entry| entry.matches(&query))
+    }
+
+    /// Saves the current mission to the project history.
+    pub fn record_mission(
+        &self,
+        actions: Vec<ProposedAction>,
+        outcome: MissionOutcome,
+        commit_hash: Option<String>,
+    ) -> Result<()> {
+        let message = self.get_input_value_from_focused(FocusedBlock::Message);
+        let context_paths = self
+            .context_items
+            .items
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect();
+        let mut entry = HistoryEntry::new(
+            &message,
+            context_paths,
+            self.get_current_execution_id(),
+            actions,
+            outcome,
+        );
+        if let Some(commit_hash) = commit_hash {
+            entry = entry.with_commit_hash(commit_hash);
+        }
+        append_history_entry(&self.project_dir, &entry)
+    }
+
+    /// Shows the actions of a new execution. Without feedback it starts a new revision chain.
+    pub fn push_revision(
+        &mut self,
+        execution: Execution,
+        feedback: Option<String>,
+        snapshot: ProjectSnapshot,
+    ) {
+        match feedback {
+            Some(_) => self.save_current_revision(),
+            None => self.action_revisions.clear(),
+        }
+        self.set_action_items(execution.actions);
+        self.set_current_execution_id(Some(execution.execution_id.clone()));
+        self.action_revisions.push(ActionRevision {
+            execution_id: execution.execution_id,
+            feedback,
+            action_items: self.action_items.clone(),
+            snapshot,
+        });
+        self.current_revision = self.action_revisions.len() - 1;
+    }
+
+    /// Returns the snapshot taken when the current actions were proposed.
+    pub fn current_snapshot(&self) -> ProjectSnapshot {
+        match self.action_revisions.get(self.current_revision) {
+            Some(revision) => revision.snapshot.clone(),
+            None => ProjectSnapshot::new(),
+        }
+    }
+
+    /// Goes back (or forth) to another revision, keeping the changes made to the current one.
+    pub fn select_revision(&mut self, index: usize) {
+        if index == self.current_revision || index >= self.action_revisions.len() {
+            return;
+        }
+        self.save_current_revision();
+        let revision = self.action_revisions[index].clone();
+        self.action_items = revision.action_items;
+        self.set_current_execution_id(Some(revision.execution_id));
+        self.current_revision = index;
+        self.preview_scroll = 0;
+        self.selected_hunk = 0;
+        let description = match revision.feedback {
+            Some(feedback) => format!("after \"{}\"", feedback),
+            None => String::from("as first proposed"),
+        };
+        self.set_header_status(HeaderStatus::SuccessMessage(format!(
+            "Revision {}/{} {}",
+            index + 1,
+            self.action_revisions.len(),
+            description
+        )));
+    }
+
+    // checked actions and rejected hunks are kept per revision
+    fn save_current_revision(&mut self) {
+        if let Some(revision) = self.action_revisions.get_mut(self.current_revision) {
+            revision.action_items = self.action_items.clone();
+        }
+    }
+
+    /// Removes the revision chain, returning the execution ids of all but the current revision.
+    pub fn take_other_revision_execution_ids(&mut self) -> Vec<String> {
+        let current_revision = self.current_revision;
+        self.current_revision = 0;
+        std::mem::take(&mut self.action_revisions)
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| *index != current_revision)
+            .map(|(_, revision)| revision.execution_id)
+            .collect()
+    }
+
     pub fn get_current_execution_id(&self) -> Option<String> {
         self.execution_id.clone()
     }
@@ -223,14 +460,49 @@ impl AppState {
         Ok(project_files)
     }
 
+    /// Tokens of the message, the context files and the project file list sent with a mission.
+    pub fn mission_tokens(&mut self) -> usize {
+        if self.project_files_tokens.is_none() {
+            let project_files = self.get_project_files().unwrap_or_default();
+            self.project_files_tokens = Some(self.token_counter.count(&project_files.join("\n")));
+        }
+        let message = self.get_input_value_from_focused(FocusedBlock::Message);
+        self.token_counter.count(&message)
+            + self.context_tokens()
+            + self.project_files_tokens.unwrap_or(0)
+    }
+
+    // the header draws the count on every tick, the items are only counted again once they change
+    fn context_tokens(&mut self) -> usize {
+        let mut hasher = DefaultHasher::new();
+        self.context_items.items.hash(&mut hasher);
+        let hash = hasher.finish();
+        if let Some((counted_hash, tokens)) = self.context_tokens {
+            if counted_hash == hash {
+                return tokens;
+            }
+        }
+        let tokens = self
+            .context_items
+            .items
+            .iter()
+            .filter_map(|(item, output)| {
+                self.token_counter
+                    .count_context_item(&self.project_dir, item, output)
+            })
+            .sum();
+        self.context_tokens = Some((hash, tokens));
+        tokens
+    }
+
     pub fn get_base_branch_name(&self) -> Option<String> {
         let key = format!("base_branch_name_{}", self.project_dir);
-        configjson::get(&key)
+        config::get(&key)
     }
 
     pub fn set_base_branch_name(&self, base_branch_name: &str) -> Result<()> {
         let key = format!("base_branch_name_{}", self.project_dir);
-        match configjson::set(&key, base_branch_name) {
+        match config::set(&key, base_branch_name) {
             Ok(_) => Ok(()),
             Err(err) => {
                 anyhow::bail!("Failed to set base branch name: {:?}", err);
@@ -240,7 +512,7 @@ impl AppState {
 
     pub fn delete_base_branch_name(&self) -> Result<()> {
         let key = format!("base_branch_name_{}", self.project_dir);
-        match configjson::delete(&key) {
+        match config::delete(&key) {
             Ok(_) => Ok(()),
             Err(err) => {
                 anyhow::bail!("Failed to delete base branch name: {:?}", err);
@@ -250,7 +522,7 @@ impl AppState {
 
     pub async fn set_execution_fail(&self) -> Result<()> {
         if let Some(execution_id) = self.execution_id.clone() {
-            match set_fail(SetFailRequest { execution_id }).await {
+            match self.backend.set_fail(SetFailRequest { execution_id }).await {
                 Ok(_) => return Ok(()),
                 Err(err) => {
                     anyhow::bail!("Failed to fail execution: {:?}", err.message);
@@ -296,6 +568,16 @@ impl App {
                 Mutex::new(AppView::AddContextFiles(AddContextFilesView::new())),
             );
 
+            views.insert(
+                AppScreen::History,
+                Mutex::new(AppView::History(HistoryView::new())),
+            );
+
+            views.insert(
+                AppScreen::Presets,
+                Mutex::new(AppView::Presets(PresetsView::new())),
+            );
+
             views
         };
         Ok(Self { state, views })
@@ -314,10 +596,71 @@ impl App {
         Ok(())
     }
 
-    pub async fn handle_events(&mut self) -> Result<bool> {
+    /// Returns true once after the Actions block asked to edit the selected action.
+    pub fn take_edit_action_request(&mut self) -> bool {
         let state = self.state.get_mut().unwrap();
+        std::mem::take(&mut state.edit_action_requested)
+    }
+
+    /// Replaces the content of the selected action with the one edited in `$VISUAL`/`$EDITOR`.
+    pub fn edit_selected_action(&mut self) {
+        let state = self.state.get_mut().unwrap();
+        let project_dir = state.project_dir.clone();
+        let item = match state.action_items.get_selected_item_mut() {
+            Some(item) => item,
+            None => return,
+        };
+        let content = action_proposed_content(&project_dir, &item.action);
+        let file_path = item
+            .action
+            .new_path
+            .clone()
+            .unwrap_or(item.action.path.clone());
+        match edit_in_external_editor(&content, &file_path) {
+            Ok(edited_content) if edited_content == content => {}
+            Ok(edited_content) => {
+                item.action.content = Some(edited_content);
+                // hunk indexes point into the old content
+                item.rejected_hunks.clear();
+                state.preview_scroll = 0;
+                state.selected_hunk = 0;
+                state
+                    .set_header_status(HeaderStatus::SuccessMessage(String::from("Action edited")));
+            }
+            Err(err) => {
+                state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
+            }
+        }
+    }
+
+    pub async fn handle_event(&mut self, event: AppEvent) -> Result<bool> {
+        let state = self.state.get_mut().unwrap();
+
+        if let AppEvent::Tick = event {
+            state.ticks = state.ticks.wrapping_add(1);
+            return Ok(false);
+        }
+
+        if let AppEvent::Mission(task_id, event) = event {
+            handle_mission_event(state, task_id, event);
+            return Ok(false);
+        }
+
+        if let AppEvent::Verification(event) = event {
+            handle_verification_event(state, event).await;
+            return Ok(false);
+        }
+
+        if let AppEvent::ContextCommand(item, output) = event {
+            handle_context_command_event(state, item, output);
+            return Ok(false);
+        }
+
+        if let AppEvent::Key(key) = event {
+            if handle_mission_shortcuts(state, &key)? == ShortcutHandlerResponse::StopPropagation {
+                return Ok(false);
+            }
 
-        if let Event::Key(key) = event::read()? {
             let response = self
                 .views
                 .get(&state.screen)
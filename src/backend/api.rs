@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use lilicore::{
+    auth::{auth_introspect_token, auth_login, AuthLoginRequest, KeycloakDecodedAccessToken},
+    code_missions_api::{
+        api_delete, api_get, api_post, ApiError, CreateMissionRequest, CreateMissionResponse,
+        EmptyApiResponse, ExecuteMissionRequest, MissionExecution, ReviewActionsRequest,
+        SetApprovedRequest, SetFailRequest,
+    },
+    rate_limit::RateLimitMe,
+};
+
+use crate::{config, missions::Execution};
+
+use super::MissionBackend;
+
+/// The hosted Lili service.
+///
+/// The auth tokens are kept in the lili config dir. lilicore's api functions read the token
+/// from its own hard-coded config file, so the requests are sent with lilicore's api client
+/// and the token of the config dir instead.
+#[derive(Debug)]
+pub struct ApiBackend;
+
+fn access_token() -> String {
+    config::get("access_token").unwrap_or_default()
+}
+
+fn empty_response(uri: &str) -> ApiError {
+    ApiError {
+        status_code: 500,
+        message: format!("No response from {}", uri),
+    }
+}
+
+#[async_trait]
+impl MissionBackend for ApiBackend {
+    async fn create_mission(
+        &self,
+        request: CreateMissionRequest,
+    ) -> Result<CreateMissionResponse, ApiError> {
+        let uri = "/missions/create";
+        api_post::<CreateMissionRequest, CreateMissionResponse>(&access_token(), uri, &request)
+            .await?
+            .ok_or_else(|| empty_response(uri))
+    }
+
+    async fn execute_mission(&self, request: ExecuteMissionRequest) -> Result<Execution, ApiError> {
+        let uri = "/missions/execute";
+        let execution =
+            api_post::<ExecuteMissionRequest, MissionExecution>(&access_token(), uri, &request)
+                .await?
+                .ok_or_else(|| empty_response(uri))?;
+        Ok(Execution::from(execution))
+    }
+
+    async fn set_approved(&self, request: SetApprovedRequest) -> Result<(), ApiError> {
+        let uri = format!("/executions/{}/approve", request.execution_id);
+        api_post::<SetApprovedRequest, ()>(&access_token(), &uri, &request).await?;
+        Ok(())
+    }
+
+    async fn review_actions(&self, request: ReviewActionsRequest) -> Result<(), ApiError> {
+        let uri = format!("/executions/{}/review_actions", request.execution_id);
+        api_post::<ReviewActionsRequest, EmptyApiResponse>(&access_token(), &uri, &request).await?;
+        Ok(())
+    }
+
+    async fn set_fail(&self, request: SetFailRequest) -> Result<(), ApiError> {
+        let uri = format!("/executions/{}", request.execution_id);
+        api_delete(&access_token(), &uri).await
+    }
+
+    async fn rate_limit_get(&self) -> Result<RateLimitMe, ApiError> {
+        let uri = "/ratelimit/me";
+        api_get::<RateLimitMe>(&access_token(), uri)
+            .await?
+            .ok_or_else(|| empty_response(uri))
+    }
+
+    async fn get_signed_in_user_name(&self) -> Option<String> {
+        let access_token = config::get("access_token")?;
+        // validate access token
+        match auth_introspect_token(&access_token).await {
+            Ok(introspected) => {
+                if !introspected.active {
+                    return None;
+                }
+            }
+            Err(_err) => {
+                // user is not signed in
+                return None;
+            }
+        };
+        // get user name from current access token
+        let user_name = match KeycloakDecodedAccessToken::new(&access_token) {
+            Ok(decoded) => decoded.get_user_name().unwrap_or(String::from("Guest")),
+            Err(_err) => String::from("Guest"),
+        };
+        Some(user_name)
+    }
+
+    async fn sign_in(&self, request: AuthLoginRequest) -> Result<String, String> {
+        let response = match auth_login(request).await {
+            Ok(response) => response,
+            Err(err) => return Err(format!("{:?}", err.error_description)),
+        };
+        let saved = config::set("access_token", &response.access_token)
+            .and_then(|_| config::set("refresh_token", &response.refresh_token));
+        if let Err(err) = saved {
+            return Err(format!("Failed to save the auth tokens: {}", err));
+        }
+        match response.decode_access_token() {
+            Ok(decoded) => Ok(decoded.given_name),
+            Err(err) => Err(format!("{:?}", err)),
+        }
+    }
+}
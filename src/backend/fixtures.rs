@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use lilicore::{
+    auth::AuthLoginRequest,
+    code_missions_api::{
+        ApiError, CreateMissionRequest, CreateMissionResponse, ExecuteMissionRequest,
+        ReviewActionsRequest, SetApprovedRequest, SetFailRequest,
+    },
+    rate_limit::RateLimitMe,
+};
+use serde::de::DeserializeOwned;
+
+use crate::missions::Execution;
+
+use super::MissionBackend;
+
+/// Offline backend that answers with canned responses read from a directory:
+///
+/// - `create_mission.json`: a `CreateMissionResponse`
+/// - `execute_mission.json`: a `MissionExecution`, its actions may also delete or rename files
+/// - `rate_limit.json`: a `RateLimitMe` (optional)
+#[derive(Debug)]
+pub struct FixtureBackend {
+    fixtures_dir: PathBuf,
+}
+
+impl FixtureBackend {
+    pub fn new(fixtures_dir: &str) -> Self {
+        Self {
+            fixtures_dir: PathBuf::from(fixtures_dir),
+        }
+    }
+
+    fn read_fixture<T: DeserializeOwned>(&self, file_name: &str) -> Result<T, ApiError> {
+        let path = self.fixtures_dir.join(file_name);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                return Err(ApiError {
+                    status_code: 404,
+                    message: format!("Failed to read fixture {}: {}", path.display(), err),
+                })
+            }
+        };
+        match serde_json::from_str::<T>(&content) {
+            Ok(response) => Ok(response),
+            Err(err) => Err(ApiError {
+                status_code: 500,
+                message: format!("Invalid fixture {}: {}", path.display(), err),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl MissionBackend for FixtureBackend {
+    async fn create_mission(
+        &self,
+        _request: CreateMissionRequest,
+    ) -> Result<CreateMissionResponse, ApiError> {
+        self.read_fixture("create_mission.json")
+    }
+
+    async fn execute_mission(
+        &self,
+        _request: ExecuteMissionRequest,
+    ) -> Result<Execution, ApiError> {
+        self.read_fixture("execute_mission.json")
+    }
+
+    async fn set_approved(&self, _request: SetApprovedRequest) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    async fn review_actions(&self, _request: ReviewActionsRequest) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    async fn set_fail(&self, _request: SetFailRequest) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    async fn rate_limit_get(&self) -> Result<RateLimitMe, ApiError> {
+        self.read_fixture("rate_limit.json")
+    }
+
+    async fn get_signed_in_user_name(&self) -> Option<String> {
+        Some(String::from("Offline"))
+    }
+
+    async fn sign_in(&self, request: AuthLoginRequest) -> Result<String, String> {
+        Ok(request.username)
+    }
+}
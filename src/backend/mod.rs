@@ -0,0 +1,92 @@
+use std::{fmt::Debug, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use lilicore::{
+    auth::AuthLoginRequest,
+    code_missions_api::{
+        ApiError, CreateMissionRequest, CreateMissionResponse, ExecuteMissionRequest,
+        ReviewActionsRequest, SetApprovedRequest, SetFailRequest,
+    },
+    rate_limit::RateLimitMe,
+};
+
+use crate::{config, missions::Execution};
+
+mod api;
+mod fixtures;
+mod openai;
+
+pub use api::*;
+pub use fixtures::*;
+pub use openai::*;
+
+/// Everything the app needs from the service that generates missions.
+#[async_trait]
+pub trait MissionBackend: Debug + Send + Sync {
+    async fn create_mission(
+        &self,
+        request: CreateMissionRequest,
+    ) -> Result<CreateMissionResponse, ApiError>;
+
+    async fn execute_mission(&self, request: ExecuteMissionRequest) -> Result<Execution, ApiError>;
+
+    async fn set_approved(&self, request: SetApprovedRequest) -> Result<(), ApiError>;
+
+    /// Reports the actions the user kept when only part of the execution was approved.
+    async fn review_actions(&self, request: ReviewActionsRequest) -> Result<(), ApiError>;
+
+    async fn set_fail(&self, request: SetFailRequest) -> Result<(), ApiError>;
+
+    async fn rate_limit_get(&self) -> Result<RateLimitMe, ApiError>;
+
+    /// Returns the name of the signed in user, or None when nobody is signed in.
+    async fn get_signed_in_user_name(&self) -> Option<String>;
+
+    /// Signs in and returns the name of the user.
+    async fn sign_in(&self, request: AuthLoginRequest) -> Result<String, String>;
+
+    /// The model missions are sent to, tokens are counted with its tokenizer.
+    fn model(&self) -> String {
+        String::from(HOSTED_MODEL)
+    }
+}
+
+// the model behind the hosted Lili service
+const HOSTED_MODEL: &str = "gpt-4";
+
+/// Reads a setting from the `LILI_<KEY>` environment variable or from the config file.
+pub fn get_setting(key: &str) -> Option<String> {
+    match std::env::var(format!("LILI_{}", key.to_uppercase())) {
+        Ok(value) => Some(value),
+        Err(_) => config::get(key),
+    }
+}
+
+/// Creates the backend selected by the `backend` setting (`api`, `fixtures` or `openai`).
+pub fn backend_from_settings() -> Result<Arc<dyn MissionBackend>> {
+    let backend = get_setting("backend").unwrap_or(String::from("api"));
+    match backend.as_str() {
+        "api" => Ok(Arc::new(ApiBackend)),
+        "fixtures" => {
+            let fixtures_dir = match get_setting("fixtures_dir") {
+                Some(fixtures_dir) => fixtures_dir,
+                None => anyhow::bail!("The fixtures backend needs the fixtures_dir setting"),
+            };
+            Ok(Arc::new(FixtureBackend::new(&fixtures_dir)))
+        }
+        "openai" => {
+            let base_url = match get_setting("llm_base_url") {
+                Some(base_url) => base_url,
+                None => anyhow::bail!("The openai backend needs the llm_base_url setting"),
+            };
+            let model = match get_setting("llm_model") {
+                Some(model) => model,
+                None => anyhow::bail!("The openai backend needs the llm_model setting"),
+            };
+            let api_key = get_setting("llm_api_key");
+            Ok(Arc::new(OpenAiBackend::new(&base_url, &model, api_key)))
+        }
+        _ => anyhow::bail!("Unknown backend: {}", backend),
+    }
+}
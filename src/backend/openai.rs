@@ -0,0 +1,520 @@
+use std::{
+    path::{Component, Path},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use lilicore::{
+    auth::AuthLoginRequest,
+    code_missions_api::{
+        ApiError, CodeMissionStatus, CreateMissionRequest, CreateMissionResponse,
+        ExecuteMissionRequest, MissionData, MissionExecutionContextFile, ReviewActionsRequest,
+        SetApprovedRequest, SetFailRequest,
+    },
+    rate_limit::RateLimitMe,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::missions::{ActionType, Execution, ProposedAction};
+
+use super::MissionBackend;
+
+const CONTEXT_FILES_PROMPT: &str = "You are a senior software engineer. \
+Given a task and the list of files of a project, answer with a JSON array containing \
+the paths of the files that must be read or changed to complete the task. \
+Answer only with the JSON array.";
+
+const EXECUTE_PROMPT: &str = "You are a senior software engineer. \
+Complete the task by creating, updating, deleting or renaming files of the project. \
+Answer only with a JSON object in this format: \
+{\"actions\": [{\"action_type\": \"CreateFile\", \"UpdateFile\", \"DeleteFile\" or \"RenameFile\", \
+\"path\": \"relative/path\", \"content\": \"the full new content of the file\", \
+\"new_path\": \"relative/new/path\"}]}. \
+DeleteFile actions have no content. RenameFile actions need the new_path, and only have \
+a content when the moved file must also change.";
+
+/// Backend for self-hosted models behind an OpenAI compatible `/v1/chat/completions` endpoint.
+///
+/// The prompts are built locally, so no code is sent to the hosted Lili service.
+#[derive(Debug)]
+pub struct OpenAiBackend {
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatCompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChoice {
+    message: ChatMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActionsReply {
+    actions: Vec<ProposedAction>,
+}
+
+impl OpenAiBackend {
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn chat(&self, system_prompt: &str, user_prompt: String) -> Result<String, ApiError> {
+        let request = ChatCompletionRequest {
+            model: self.model.clone(),
+            messages: vec![
+                ChatMessage {
+                    role: String::from("system"),
+                    content: system_prompt.to_string(),
+                },
+                ChatMessage {
+                    role: String::from("user"),
+                    content: user_prompt,
+                },
+            ],
+            temperature: 0.0,
+        };
+        let mut http_request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&request);
+        if let Some(api_key) = &self.api_key {
+            http_request = http_request.bearer_auth(api_key);
+        }
+        let response = match http_request.send().await {
+            Ok(response) => response,
+            Err(err) => return Err(ApiError::from(err, "Failed to reach the llm server")),
+        };
+        let response = match response.error_for_status() {
+            Ok(response) => response,
+            Err(err) => return Err(ApiError::from(err, "The llm server returned an error")),
+        };
+        let response = match response.json::<ChatCompletionResponse>().await {
+            Ok(response) => response,
+            Err(err) => return Err(ApiError::from(err, "Invalid response from the llm server")),
+        };
+        match response.choices.into_iter().next() {
+            Some(choice) => Ok(choice.message.content),
+            None => Err(ApiError {
+                status_code: 500,
+                message: String::from("The llm server returned no choices"),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl MissionBackend for OpenAiBackend {
+    async fn create_mission(
+        &self,
+        request: CreateMissionRequest,
+    ) -> Result<CreateMissionResponse, ApiError> {
+        let mission_data = &request.mission_data;
+        let reply = self
+            .chat(CONTEXT_FILES_PROMPT, build_context_files_prompt(mission_data))
+            .await?;
+        let context_files = parse_context_files(&reply, &mission_data.project_files)?;
+        Ok(CreateMissionResponse {
+            mission_id: local_id("mission"),
+            mission_status: CodeMissionStatus::Created,
+            context_files,
+        })
+    }
+
+    async fn execute_mission(&self, request: ExecuteMissionRequest) -> Result<Execution, ApiError> {
+        let reply = self
+            .chat(
+                EXECUTE_PROMPT,
+                build_execute_prompt(&request.mission_data, &request.context_files),
+            )
+            .await?;
+        Ok(Execution {
+            execution_id: local_id("execution"),
+            actions: parse_actions(&reply, &request.mission_data.project_files)?,
+        })
+    }
+
+    async fn set_approved(&self, _request: SetApprovedRequest) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    async fn review_actions(&self, _request: ReviewActionsRequest) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    async fn set_fail(&self, _request: SetFailRequest) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    async fn rate_limit_get(&self) -> Result<RateLimitMe, ApiError> {
+        Err(ApiError {
+            status_code: 404,
+            message: String::from("The local llm backend has no rate limit"),
+        })
+    }
+
+    async fn get_signed_in_user_name(&self) -> Option<String> {
+        Some(self.model.clone())
+    }
+
+    async fn sign_in(&self, _request: AuthLoginRequest) -> Result<String, String> {
+        Err(String::from("Signing in is not needed with the local llm backend"))
+    }
+
+    fn model(&self) -> String {
+        self.model.clone()
+    }
+}
+
+fn build_context_files_prompt(mission_data: &MissionData) -> String {
+    format!(
+        "Language: {}\nFramework: {}\n\nProject files:\n{}\n\nTask: {}",
+        mission_data.code_language,
+        mission_data.framework,
+        mission_data.project_files.join("\n"),
+        mission_data.message,
+    )
+}
+
+fn build_execute_prompt(
+    mission_data: &MissionData,
+    context_files: &[MissionExecutionContextFile],
+) -> String {
+    let mut prompt = format!(
+        "Language: {}\nFramework: {}\n\nProject files:\n{}\n",
+        mission_data.code_language,
+        mission_data.framework,
+        mission_data.project_files.join("\n"),
+    );
+    for context_file in context_files {
+        prompt.push_str(&format!(
+            "\nFile: {}\n```\n{}\n```\n",
+            context_file.path, context_file.content
+        ));
+    }
+    prompt.push_str(&format!("\nTask: {}", mission_data.message));
+    prompt
+}
+
+/// Models often wrap the answer in markdown and prose, so the first valid json value
+/// starting with `open` is parsed, up to its balanced closing bracket.
+fn extract_json(reply: &str, open: char, close: char) -> Option<&str> {
+    reply
+        .match_indices(open)
+        .filter_map(|(start, _)| balanced_json(&reply[start..], close))
+        .find(|json| serde_json::from_str::<serde_json::Value>(json).is_ok())
+}
+
+// the text up to the bracket closing the one it starts with, brackets in strings ignored
+fn balanced_json(text: &str, close: char) -> Option<&str> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return match c == close {
+                        true => Some(&text[..=index]),
+                        false => None,
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// None for paths leaving the project, like `../x`
+fn normalize_path(path: &str) -> Option<String> {
+    let path = path
+        .trim()
+        .trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string();
+    let leaves_project = Path::new(&path)
+        .components()
+        .any(|component| component == Component::ParentDir);
+    match leaves_project {
+        true => None,
+        false => Some(path),
+    }
+}
+
+fn parse_context_files(reply: &str, project_files: &[String]) -> Result<Vec<String>, ApiError> {
+    let json = extract_json(reply, '[', ']').unwrap_or("[]");
+    let paths = match serde_json::from_str::<Vec<String>>(json) {
+        Ok(paths) => paths,
+        Err(err) => {
+            return Err(ApiError {
+                status_code: 500,
+                message: format!("The llm answered with invalid context files: {}", err),
+            })
+        }
+    };
+    Ok(paths
+        .iter()
+        .filter_map(|path| normalize_path(path))
+        .filter(|path| project_files.is_empty() || project_files.contains(path))
+        .collect())
+}
+
+fn invalid_action(action: &ProposedAction, reason: &str) -> ApiError {
+    ApiError {
+        status_code: 500,
+        message: format!(
+            "The llm answered with an invalid {} action on {}: {}",
+            action.action_type, action.path, reason
+        ),
+    }
+}
+
+// only files of the project can be updated, deleted or renamed, and nothing outside of it;
+// the actions must also have what applying them needs
+fn check_action(
+    action: ProposedAction,
+    project_files: &[String],
+) -> Result<ProposedAction, ApiError> {
+    let path = match normalize_path(&action.path) {
+        Some(path) => path,
+        None => return Err(invalid_action(&action, "the path leaves the project")),
+    };
+    match action.action_type {
+        ActionType::CreateFile | ActionType::UpdateFile if action.content.is_none() => {
+            return Err(invalid_action(&action, "the content is missing"))
+        }
+        ActionType::RenameFile if action.new_path.is_none() => {
+            return Err(invalid_action(&action, "the new path is missing"))
+        }
+        _ => {}
+    }
+    let new_path = match action.new_path.as_deref().map(normalize_path) {
+        Some(None) => return Err(invalid_action(&action, "the new path leaves the project")),
+        Some(new_path) => new_path,
+        None => None,
+    };
+    let must_exist = action.action_type != ActionType::CreateFile;
+    if must_exist && !project_files.is_empty() && !project_files.contains(&path) {
+        return Err(invalid_action(
+            &action,
+            "the file is not part of the project",
+        ));
+    }
+    Ok(ProposedAction {
+        path,
+        new_path,
+        ..action
+    })
+}
+
+fn parse_actions(reply: &str, project_files: &[String]) -> Result<Vec<ProposedAction>, ApiError> {
+    let json = match extract_json(reply, '{', '}') {
+        Some(json) => json,
+        None => {
+            return Err(ApiError {
+                status_code: 500,
+                message: String::from("The llm did not answer with actions"),
+            })
+        }
+    };
+    match serde_json::from_str::<ActionsReply>(json) {
+        Ok(reply) => reply
+            .actions
+            .into_iter()
+            .map(|action| check_action(action, project_files))
+            .collect(),
+        Err(err) => Err(ApiError {
+            status_code: 500,
+            message: format!("The llm answered with invalid actions: {}", err),
+        }),
+    }
+}
+
+fn unix_timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default()
+}
+
+fn local_id(prefix: &str) -> String {
+    format!("local-{}-{}", prefix, unix_timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use lilicore::code_missions_api::{ExecuteMissionRequest, MissionData};
+    use serde_json::json;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    // answers the next request on a local port with the reply as the chat completion
+    async fn serve_reply(reply: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let body = json!({
+            "choices": [{ "message": { "role": "assistant", "content": reply } }]
+        })
+        .to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let mut buffer = [0; 4096];
+            // the body is read too, closing early would reset the connection
+            while !request_complete(&request) {
+                let read = stream.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let head = String::from_utf8_lossy(&request);
+            let status = match head.starts_with("POST /v1/chat/completions ") {
+                true => "200 OK",
+                false => "404 Not Found",
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+        base_url
+    }
+
+    fn request_complete(request: &[u8]) -> bool {
+        let text = String::from_utf8_lossy(request);
+        let head_end = match text.find("\r\n\r\n") {
+            Some(head_end) => head_end,
+            None => return false,
+        };
+        let content_length = text[..head_end]
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        request.len() >= head_end + 4 + content_length
+    }
+
+    fn execute_request() -> ExecuteMissionRequest {
+        ExecuteMissionRequest {
+            mission_id: String::from("mission"),
+            mission_data: MissionData {
+                project_dir: String::from("/tmp/project"),
+                message: String::from("Rename the helpers"),
+                project_files: vec![String::from("src/main.rs"), String::from("src/old.rs")],
+                code_language: Default::default(),
+                framework: Default::default(),
+            },
+            context_files: vec![],
+        }
+    }
+
+    async fn execute(reply: &str) -> Result<Execution, ApiError> {
+        let base_url = serve_reply(reply).await;
+        OpenAiBackend::new(&base_url, "local-model", None)
+            .execute_mission(execute_request())
+            .await
+    }
+
+    #[tokio::test]
+    async fn parses_the_actions_of_a_chat_completion() {
+        let reply = r#"Sure, here are the actions:
+```json
+{"actions": [
+    {"action_type": "UpdateFile", "path": "./src/main.rs", "content": "fn main() { println!(\"}\"); }"},
+    {"action_type": "RenameFile", "path": "src/old.rs", "new_path": "src/new.rs"},
+    {"action_type": "CreateFile", "path": "src/lib.rs", "content": ""}
+]}
+```
+Let me know if the `{}` in main or the [old] module need more changes}"#;
+        let actions = execute(reply).await.unwrap().actions;
+
+        assert_eq!(actions.len(), 3);
+        assert_eq!(actions[0].action_type, ActionType::UpdateFile);
+        assert_eq!(actions[0].path, "src/main.rs");
+        assert_eq!(
+            actions[0].content.as_deref(),
+            Some("fn main() { println!(\"}\"); }")
+        );
+        assert_eq!(actions[1].action_type, ActionType::RenameFile);
+        assert_eq!(actions[1].path, "src/old.rs");
+        assert_eq!(actions[1].new_path.as_deref(), Some("src/new.rs"));
+        assert_eq!(actions[1].content, None);
+        assert_eq!(actions[2].action_type, ActionType::CreateFile);
+        assert_eq!(actions[2].path, "src/lib.rs");
+    }
+
+    #[tokio::test]
+    async fn rejects_actions_outside_of_the_project() {
+        let replies = [
+            r#"{"actions": [{"action_type": "DeleteFile", "path": "../../.ssh/authorized_keys"}]}"#,
+            r#"{"actions": [{"action_type": "CreateFile", "path": "src/../../x.rs", "content": ""}]}"#,
+            r#"{"actions": [{"action_type": "RenameFile", "path": "src/old.rs", "new_path": "../old.rs"}]}"#,
+            r#"{"actions": [{"action_type": "UpdateFile", "path": "src/unknown.rs", "content": ""}]}"#,
+        ];
+        for reply in replies {
+            assert!(execute(reply).await.is_err(), "{}", reply);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_file_actions_without_content() {
+        let replies = [
+            r#"{"actions": [{"action_type": "UpdateFile", "path": "src/main.rs"}]}"#,
+            r#"{"actions": [{"action_type": "CreateFile", "path": "src/lib.rs"}]}"#,
+        ];
+        for reply in replies {
+            assert!(execute(reply).await.is_err(), "{}", reply);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_renames_without_new_path() {
+        let reply = r#"{"actions": [{"action_type": "RenameFile", "path": "src/old.rs"}]}"#;
+        assert!(execute(reply).await.is_err());
+    }
+}
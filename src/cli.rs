@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use crate::{app::AppScreen, headless::RunOptions};
+
+/// AI Assistant for Developers
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Project directory, defaults to the current directory
+    #[arg(value_name = "PROJECT_DIR")]
+    pub project_dir: Option<PathBuf>,
+
+    /// Project directory, same as PROJECT_DIR
+    #[arg(long, global = true, value_name = "DIR")]
+    pub project: Option<PathBuf>,
+
+    /// Directory of config.json, defaults to ~/.lili
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Do not ask to create a temporary branch on start, `run --apply` then commits on the
+    /// current branch
+    #[arg(long, global = true)]
+    pub no_temp_branch: bool,
+
+    /// Branch the temporary branch is created from and merged back into
+    #[arg(long, value_name = "NAME")]
+    pub base_branch: Option<String>,
+
+    /// Screen to open on start
+    #[arg(long, value_enum)]
+    pub screen: Option<AppScreen>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Execute a single mission without the terminal ui
+    Run(RunOptions),
+}
+
+impl Cli {
+    /// Returns the absolute project dir, failing when it is not a git repository.
+    pub fn resolve_project_dir(&self) -> Result<String> {
+        let project_dir = match self.project.as_ref().or(self.project_dir.as_ref()) {
+            Some(project_dir) => project_dir.clone(),
+            None => std::env::current_dir()?,
+        };
+        if !project_dir.is_dir() {
+            anyhow::bail!(
+                "Project directory does not exist: {}",
+                project_dir.display()
+            );
+        }
+        let project_dir = project_dir.canonicalize()?;
+        if !is_git_repository(&project_dir) {
+            anyhow::bail!(
+                "Project directory is not a git repository: {}",
+                project_dir.display()
+            );
+        }
+        match project_dir.to_str() {
+            Some(project_dir) => Ok(project_dir.trim_end_matches('/').to_string()),
+            None => anyhow::bail!("Project directory is not valid utf-8"),
+        }
+    }
+}
+
+fn is_git_repository(dir: &Path) -> bool {
+    std::process::Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(dir)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
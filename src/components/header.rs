@@ -8,10 +8,12 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::AppState;
+use crate::{app::AppState, utils::llm_tokens::format_tokens};
 
 use super::{AppComponent, DrawableComponent};
 
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 #[derive(Debug, PartialEq, Clone, Default)]
 pub enum HeaderStatus {
     #[default]
@@ -41,6 +43,7 @@ impl DrawableComponent for HeaderComponent {
         frame: &mut Frame<B>,
         rect: Rect,
     ) -> Result<()> {
+        let tokens = state.mission_tokens();
         let mut texts: Vec<Span> = vec![];
         texts.push(Span::styled(
             state.project_dir.split("/").last().unwrap(),
@@ -49,6 +52,22 @@ impl DrawableComponent for HeaderComponent {
         texts.push(Span::raw("        "));
         texts.push(Span::raw("        "));
 
+        if let Some(mission_task) = &state.mission_task {
+            let elapsed = mission_task.phase_started_at.elapsed();
+            // one frame per tick, the screen is not drawn more often while waiting
+            let frame_index = state.ticks % SPINNER_FRAMES.len();
+            texts.push(Span::styled(
+                format!(
+                    "{} {} {}s",
+                    SPINNER_FRAMES[frame_index],
+                    mission_task.phase,
+                    elapsed.as_secs()
+                ),
+                ratatui::style::Style::default().fg(ratatui::style::Color::LightCyan),
+            ));
+            texts.push(Span::raw("    "));
+        }
+
         let loading_text = match state.header_status {
             HeaderStatus::Idle => "Idle",
             HeaderStatus::Loading => "Loading",
@@ -76,6 +95,21 @@ impl DrawableComponent for HeaderComponent {
         ));
         texts.push(Span::raw("        "));
         texts.push(Span::raw("        "));
+        let limit = state.token_counter.limit;
+        let tokens_color = match tokens * 100 / limit.max(1) {
+            0..=74 => ratatui::style::Color::DarkGray,
+            75..=99 => ratatui::style::Color::Yellow,
+            _ => ratatui::style::Color::Red,
+        };
+        texts.push(Span::styled(
+            format!(
+                "{}/{} tokens",
+                format_tokens(tokens),
+                format_tokens(limit)
+            ),
+            ratatui::style::Style::default().fg(tokens_color),
+        ));
+        texts.push(Span::raw("        "));
         texts.push(Span::styled(
             format!("{}", state.user_name),
             ratatui::style::Style::default().fg(ratatui::style::Color::DarkGray),
@@ -0,0 +1,121 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ratatui::{
+    prelude::{Backend, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    app::AppState,
+    history::{format_age, MissionOutcome},
+    utils::list::SelectableItem,
+};
+
+use super::super::{AppComponent, DrawableComponent};
+
+/// Shows everything recorded about the selected history entry.
+pub struct HistoryDetailsComponent;
+
+impl HistoryDetailsComponent {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn as_mutex(self) -> Mutex<AppComponent> {
+        Mutex::new(AppComponent::HistoryDetails(self))
+    }
+}
+
+impl DrawableComponent for HistoryDetailsComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        state: &mut AppState,
+        frame: &mut Frame<B>,
+        rect: Rect,
+    ) -> Result<()> {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Mission")
+            .padding(Padding::new(1, 1, 0, 0));
+        let entries = state.filtered_history_entries();
+        let entry = match entries.get_selected_item() {
+            Some(entry) => entry,
+            None => {
+                let text = Paragraph::new(Span::styled(
+                    "No missions found",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .block(block);
+                frame.render_widget(text, rect);
+                return Ok(());
+            }
+        };
+
+        let title_style = Style::default().add_modifier(Modifier::BOLD);
+        let outcome_color = match entry.outcome {
+            MissionOutcome::Applied | MissionOutcome::Verified => Color::Green,
+            MissionOutcome::Rejected
+            | MissionOutcome::Failed
+            | MissionOutcome::Broken
+            | MissionOutcome::RolledBack => Color::Red,
+            MissionOutcome::Proposed => Color::Yellow,
+        };
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    entry.outcome.to_string(),
+                    Style::default().fg(outcome_color),
+                ),
+                Span::raw(format!(" {}", format_age(entry.timestamp))),
+            ]),
+            Line::from(format!(
+                "Execution: {}",
+                entry.execution_id.clone().unwrap_or(String::from("-"))
+            )),
+            Line::from(format!(
+                "Commit: {}",
+                entry.commit_hash.clone().unwrap_or(String::from("-"))
+            )),
+            Line::from(""),
+            Line::from(Span::styled("Message", title_style)),
+        ];
+        lines.extend(
+            entry
+                .message
+                .lines()
+                .map(|line| Line::from(line.to_string())),
+        );
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("Context files ({})", entry.context_paths.len()),
+            title_style,
+        )));
+        lines.extend(
+            entry
+                .context_paths
+                .iter()
+                .map(|path| Line::from(path.clone())),
+        );
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("Actions ({})", entry.actions.len()),
+            title_style,
+        )));
+        lines.extend(
+            entry
+                .actions
+                .iter()
+                .map(|action| Line::from(SelectableItem::to_string(action))),
+        );
+
+        let widget = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(widget, rect);
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ratatui::{
+    prelude::{Backend, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, List, ListState},
+    Frame,
+};
+
+use crate::app::AppState;
+
+use super::super::{AppComponent, DrawableComponent};
+
+pub struct HistoryListComponent;
+
+impl HistoryListComponent {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn as_mutex(self) -> Mutex<AppComponent> {
+        Mutex::new(AppComponent::HistoryList(self))
+    }
+}
+
+impl DrawableComponent for HistoryListComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        state: &mut AppState,
+        frame: &mut Frame<B>,
+        rect: Rect,
+    ) -> Result<()> {
+        let entries = state.filtered_history_entries();
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Missions ({}/{})",
+                entries.len(),
+                state.history_entries.len()
+            ))
+            .border_style(Style::default().fg(Color::Cyan));
+        let list = List::new(entries.to_items())
+            .block(block)
+            .highlight_style(Style::default().fg(Color::Yellow))
+            .highlight_symbol("> ");
+        let list_state = &mut ListState::default().with_selected(entries.selected_index);
+        frame.render_stateful_widget(list, rect, list_state);
+        Ok(())
+    }
+}
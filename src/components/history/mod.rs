@@ -0,0 +1,2 @@
+pub mod history_details;
+pub mod history_list;
@@ -1,15 +1,24 @@
 use std::sync::Mutex;
 
 use anyhow::Result;
-use lilicore::code_missions_api::MissionAction;
 use ratatui::{
     prelude::{Backend, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
     Frame,
 };
+use similar::ChangeTag;
 
 use crate::{
-    app::{AppState, FocusedBlock},
+    app::{ActionPreviewMode, AppState, FocusedBlock},
     components::{AppComponent, DrawableComponent},
+    context_commands::item_command,
+    missions::{ActionItem, ActionType},
+    utils::{
+        diff::{action_original_content, action_proposed_content, diff_hunks},
+        line_ranges::{parse_context_item, read_context_item, LineRange},
+        list::SelectableItem,
+    },
 };
 
 pub struct ActionPreviewComponent;
@@ -23,28 +32,51 @@ impl ActionPreviewComponent {
         Mutex::new(AppComponent::ActionPreview(self))
     }
 
-    fn get_content(&self, state: &mut AppState) -> String {
+    fn get_content(&self, state: &mut AppState) -> Text<'static> {
         match state.focused_block.clone() {
+            FocusedBlock::Conflicts => get_conflicts_preview(state),
             FocusedBlock::Actions => {
-                let action = match state.action_items.get_selected_item() {
-                    Some(action) => action,
-                    None => return String::new(),
+                let item = match state.action_items.get_selected_item() {
+                    Some(item) => item,
+                    None => return Text::default(),
                 };
 
-                return action.content.clone();
+                match state.action_preview_mode {
+                    ActionPreviewMode::Content => {
+                        Text::from(action_proposed_content(&state.project_dir, &item.action))
+                    }
+                    // the side-by-side view is drawn by DiffSplitComponent
+                    _ => get_action_diff(&state.project_dir, item, None).0,
+                }
+            }
+            FocusedBlock::ActionHunks => {
+                let item = match state.action_items.get_selected_item() {
+                    Some(item) => item,
+                    None => return Text::default(),
+                };
+                let (text, selected_line) =
+                    get_action_diff(&state.project_dir, item, Some(state.selected_hunk));
+                if let Some(selected_line) = selected_line {
+                    state.preview_scroll = selected_line as u16;
+                }
+                text
             }
             FocusedBlock::ContextFiles => {
                 let context_file = match state.context_items.get_selected_item() {
+                    Some((item, output)) if item_command(item).is_some() => {
+                        return Text::from(output.clone())
+                    }
                     Some(item) => item.0.clone(),
-                    None => return String::new(),
+                    None => return Text::default(),
                 };
+                // ranges are shown as they are sent
                 let file_content =
-                    std::fs::read_to_string(format!("{}/{}", &state.project_dir, context_file))
-                        .unwrap_or_default();
-                return file_content;
+                    read_context_item(&state.project_dir, &context_file).unwrap_or_default();
+                Text::from(file_content)
             }
-            _ => return String::new(),
-        };
+            FocusedBlock::ContextRanges => get_context_ranges_preview(state),
+            _ => Text::default(),
+        }
     }
 }
 
@@ -55,17 +87,26 @@ impl DrawableComponent for ActionPreviewComponent {
         frame: &mut Frame<B>,
         rect: Rect,
     ) -> Result<()> {
-        // let action = match state.action_items.get_selected_item() {
-        //     Some(action) => action,
-        //     None => return Ok(()),
-        // };
         let content = self.get_content(state);
         let selected_title = match &state.focused_block {
             &FocusedBlock::Actions => {
                 format!(
-                    "Action ( {} )",
+                    "Action ( {} ) [{}]",
+                    match state.action_items.get_selected_item() {
+                        Some(item) => item.action.path.clone(),
+                        None => String::from(""),
+                    },
+                    match state.action_preview_mode {
+                        ActionPreviewMode::Content => "content",
+                        _ => "diff",
+                    }
+                )
+            }
+            &FocusedBlock::ActionHunks => {
+                format!(
+                    "Hunks ( {} )",
                     match state.action_items.get_selected_item() {
-                        Some(action) => action.path.clone(),
+                        Some(item) => item.action.path.clone(),
                         None => String::from(""),
                     }
                 )
@@ -79,6 +120,24 @@ impl DrawableComponent for ActionPreviewComponent {
                     }
                 )
             }
+            &FocusedBlock::ContextRanges => {
+                let path = match state.context_items.get_selected_item() {
+                    Some(item) => parse_context_item(&item.0).0.to_string(),
+                    None => String::from(""),
+                };
+                match state.range_anchor {
+                    Some(anchor) => format!(
+                        "Pick lines ( {} ) {}",
+                        path,
+                        LineRange::new(anchor, state.range_cursor)
+                    ),
+                    None => format!("Pick lines ( {} ) line {}", path, state.range_cursor),
+                }
+            }
+            &FocusedBlock::Conflicts => format!(
+                "Files changed since the actions were proposed ({})",
+                state.file_conflicts.len()
+            ),
             _ => String::from("Empty Preview"),
         };
 
@@ -86,7 +145,22 @@ impl DrawableComponent for ActionPreviewComponent {
             .borders(ratatui::widgets::Borders::ALL)
             .title(selected_title);
 
-        let text = ratatui::widgets::Paragraph::new(content).block(block);
+        // the cursor stays in sight while picking lines
+        if state.focused_block == FocusedBlock::ContextRanges {
+            let cursor = state.range_cursor.saturating_sub(1) as u16;
+            let height = rect.height.saturating_sub(2).max(1);
+            if cursor < state.preview_scroll {
+                state.preview_scroll = cursor;
+            } else if cursor >= state.preview_scroll + height {
+                state.preview_scroll = cursor + 1 - height;
+            }
+        }
+        state.preview_scroll = state
+            .preview_scroll
+            .min(content.lines.len().saturating_sub(1) as u16);
+        let text = ratatui::widgets::Paragraph::new(content)
+            .block(block)
+            .scroll((state.preview_scroll, 0));
 
         frame.render_widget(text, rect);
 
@@ -94,14 +168,167 @@ impl DrawableComponent for ActionPreviewComponent {
     }
 }
 
-// fn action_get_newest_content(action: &MissionAction) -> &str {
-//     action.content.split('\n').last().unwrap_or(&action.content)
-// }
+/// Shows the whole context file with line numbers, the lines already picked are green and the
+/// range being picked is highlighted.
+fn get_context_ranges_preview(state: &AppState) -> Text<'static> {
+    let (path, ranges) = match state.context_items.get_selected_item() {
+        Some(item) => parse_context_item(&item.0),
+        None => return Text::default(),
+    };
+    let content =
+        std::fs::read_to_string(format!("{}/{}", state.project_dir, path)).unwrap_or_default();
+    let width = content.lines().count().to_string().len();
+    let selection = LineRange::new(
+        state.range_anchor.unwrap_or(state.range_cursor),
+        state.range_cursor,
+    );
+    let lines: Vec<Line> = content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let number = index + 1;
+            let number_style = match ranges.iter().any(|range| range.contains(number)) {
+                true => Style::default().fg(Color::Green),
+                false => Style::default().fg(Color::DarkGray),
+            };
+            let line_style = match selection.contains(number) {
+                true => Style::default().add_modifier(Modifier::REVERSED),
+                false => Style::default(),
+            };
+            Line::from(vec![
+                Span::styled(format!("{:>width$} ", number), number_style),
+                Span::styled(line.to_string(), line_style),
+            ])
+        })
+        .collect();
+    Text::from(lines)
+}
 
-// fn action_get_original_content(action: &MissionAction, state: &mut AppState) -> String {
-//     let path = &action.path;
-//     let project_dir = &state.project_dir;
-//     let full_path = format!("{}/{}", project_dir, path);
-//     let original_content = std::fs::read_to_string(full_path).unwrap_or_default();
-//     original_content
-// }
+/// Lists the conflicts with their resolution, followed by the changes made on disk to the
+/// selected file.
+fn get_conflicts_preview(state: &AppState) -> Text<'static> {
+    let mut lines = vec![];
+    for (index, conflict) in state.file_conflicts.items.iter().enumerate() {
+        let selected = state.file_conflicts.selected_index == Some(index);
+        let style = match selected {
+            true => Style::default().fg(Color::Yellow),
+            false => Style::default(),
+        };
+        let cursor = if selected { "> " } else { "  " };
+        lines.push(Line::from(Span::styled(
+            format!("{}{}", cursor, SelectableItem::to_string(conflict)),
+            style,
+        )));
+    }
+    let conflict = match state.file_conflicts.get_selected_item() {
+        Some(conflict) => conflict,
+        None => return Text::from(lines),
+    };
+    lines.push(Line::from(""));
+    let base = match state.current_snapshot().remove(&conflict.path) {
+        Some(Some(file)) => file.content,
+        _ => String::new(),
+    };
+    let current = std::fs::read_to_string(format!("{}/{}", state.project_dir, conflict.path))
+        .unwrap_or_default();
+    lines.push(Line::from(Span::styled(
+        format!("Changed on disk: {}", conflict.path),
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    for hunk in diff_hunks(&base, &current) {
+        lines.push(Line::from(Span::styled(
+            hunk.header,
+            Style::default().fg(Color::Cyan),
+        )));
+        for line in hunk.lines {
+            let (sign, style) = match line.tag {
+                ChangeTag::Insert => ("+", Style::default().fg(Color::Green)),
+                ChangeTag::Delete => ("-", Style::default().fg(Color::Red)),
+                ChangeTag::Equal => (" ", Style::default()),
+            };
+            lines.push(Line::from(Span::styled(
+                format!("{}{}", sign, line.content),
+                style,
+            )));
+        }
+    }
+    Text::from(lines)
+}
+
+/// Renders the changes of the action as a colored unified diff.
+///
+/// Rejected hunks are dimmed. When a hunk is selected, the hunks get a checkbox and the
+/// index of the selected hunk header line is returned so the preview can scroll to it.
+fn get_action_diff(
+    project_dir: &str,
+    item: &ActionItem,
+    selected_hunk: Option<usize>,
+) -> (Text<'static>, Option<usize>) {
+    let original_content = action_original_content(project_dir, &item.action);
+    let proposed_content = action_proposed_content(project_dir, &item.action);
+    let hunks = diff_hunks(&original_content, &proposed_content);
+    let mut lines = vec![];
+    let summary = match item.action.action_type {
+        ActionType::DeleteFile => Some(format!("{} will be deleted", item.action.path)),
+        ActionType::RenameFile => Some(format!(
+            "{} will be moved to {}",
+            item.action.path,
+            item.action.new_path.clone().unwrap_or_default()
+        )),
+        _ => None,
+    };
+    if let Some(summary) = summary {
+        lines.push(Line::from(Span::styled(
+            summary,
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    if hunks.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No changes",
+            Style::default().fg(Color::DarkGray),
+        )));
+        return (Text::from(lines), None);
+    }
+    let mut selected_line = None;
+    for (hunk_index, hunk) in hunks.into_iter().enumerate() {
+        let rejected = item.rejected_hunks.contains(&hunk.change);
+        let header = match selected_hunk {
+            Some(selected_hunk) => {
+                let cursor = if selected_hunk == hunk_index {
+                    selected_line = Some(lines.len());
+                    "> "
+                } else {
+                    "  "
+                };
+                let checkbox = if rejected { "[ ]" } else { "[x]" };
+                format!("{}{} {}", cursor, checkbox, hunk.header)
+            }
+            None if rejected => format!("{} (rejected)", hunk.header),
+            None => hunk.header,
+        };
+        let header_style = match selected_line == Some(lines.len()) {
+            true => Style::default().fg(Color::Yellow),
+            false => Style::default().fg(Color::Cyan),
+        };
+        lines.push(Line::from(Span::styled(header, header_style)));
+        for line in hunk.lines {
+            let (sign, style) = match line.tag {
+                ChangeTag::Insert => ("+", Style::default().fg(Color::Green)),
+                ChangeTag::Delete => ("-", Style::default().fg(Color::Red)),
+                ChangeTag::Equal => (" ", Style::default()),
+            };
+            let style = match rejected && line.tag != ChangeTag::Equal {
+                true => Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT),
+                false => style,
+            };
+            lines.push(Line::from(Span::styled(
+                format!("{}{}", sign, line.content),
+                style,
+            )));
+        }
+    }
+    (Text::from(lines), selected_line)
+}
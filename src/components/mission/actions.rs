@@ -33,10 +33,23 @@ impl DrawableComponent for ActionsComponent {
         rect: Rect,
     ) -> Result<()> {
         let items = state.action_items.to_items();
+        let checked_count = state
+            .action_items
+            .items
+            .iter()
+            .filter(|item| item.checked)
+            .count();
 
-        let mut block = Block::default()
-            .borders(Borders::TOP)
-            .title(format!("Actions ({})", items.len()));
+        let revisions = match state.action_revisions.len() {
+            0 | 1 => String::new(),
+            count => format!(" revision {}/{}", state.current_revision + 1, count),
+        };
+        let mut block = Block::default().borders(Borders::TOP).title(format!(
+            "Actions ({}/{}){}",
+            checked_count,
+            items.len(),
+            revisions
+        ));
 
         let mut list = ratatui::widgets::List::new(items);
 
@@ -7,13 +7,14 @@ use std::{
 use anyhow::Result;
 use ratatui::{
     prelude::{Backend, Rect},
-    widgets::{Block, Borders, ListState},
+    text::{Line, Span},
+    widgets::{Block, Borders, ListItem, ListState},
     Frame,
 };
 
 use crate::{
     app::{AppState, FocusedBlock},
-    utils::list::SelectableList,
+    utils::{line_ranges::parse_context_item, list::SelectableList, llm_tokens::format_tokens},
 };
 
 use super::super::{AppComponent, DrawableComponent};
@@ -42,7 +43,37 @@ impl DrawableComponent for ContextFilesComponent {
         frame: &mut Frame<B>,
         rect: Rect,
     ) -> Result<()> {
-        let items = state.context_items.to_items();
+        let project_dir = state.project_dir.clone();
+        let items: Vec<ListItem> = state
+            .context_items
+            .items
+            .iter()
+            .map(|(path, output)| {
+                let tokens = match state
+                    .token_counter
+                    .count_context_item(&project_dir, path, output)
+                {
+                    Some(tokens) => format!("  {} tokens", format_tokens(tokens)),
+                    None => String::new(),
+                };
+                let (file_path, ranges) = parse_context_item(path);
+                let ranges = match ranges.is_empty() {
+                    true => String::new(),
+                    false => path[file_path.len()..].to_string(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(file_path.to_string()),
+                    Span::styled(
+                        ranges,
+                        ratatui::style::Style::default().fg(ratatui::style::Color::Cyan),
+                    ),
+                    Span::styled(
+                        tokens,
+                        ratatui::style::Style::default().fg(ratatui::style::Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
         let mut block = Block::default()
             .borders(Borders::TOP)
             .title(format!("Context Files ({})", items.len()));
@@ -0,0 +1,83 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ratatui::{
+    prelude::{Backend, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, List, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{app::AppState, utils::llm_tokens::format_tokens};
+
+use super::super::{AppComponent, DrawableComponent};
+
+/// Shows how every context file was reduced to fit the token budget, and the selected one.
+pub struct ContextReductionComponent;
+
+impl ContextReductionComponent {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn as_mutex(self) -> Mutex<AppComponent> {
+        Mutex::new(AppComponent::ContextReduction(self))
+    }
+}
+
+impl DrawableComponent for ContextReductionComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        state: &mut AppState,
+        frame: &mut Frame<B>,
+        rect: Rect,
+    ) -> Result<()> {
+        let reduction = match &state.context_reduction {
+            Some(reduction) => reduction,
+            None => return Ok(()),
+        };
+        let [list_rect, preview_rect] = *Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(reduction.files.len() as u16 + 2),
+                Constraint::Min(3),
+            ])
+            .split(rect)
+        else {
+            return Ok(());
+        };
+
+        let color = match reduction.fits() {
+            true => Color::Green,
+            false => Color::Red,
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Reduced context: {}/{} tokens",
+                format_tokens(reduction.tokens()),
+                format_tokens(reduction.budget)
+            ))
+            .border_style(Style::default().fg(color));
+        let list = List::new(reduction.files.to_items())
+            .block(block)
+            .highlight_style(Style::default().fg(Color::Yellow))
+            .highlight_symbol("> ");
+        let list_state = &mut ListState::default().with_selected(reduction.files.selected_index);
+        frame.render_stateful_widget(list, list_rect, list_state);
+
+        let file = match reduction.files.get_selected_item() {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let preview = Paragraph::new(file.content.clone())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("{} ({})", file.path, file.reduction)),
+            )
+            .scroll((state.preview_scroll, 0));
+        frame.render_widget(preview, preview_rect);
+        Ok(())
+    }
+}
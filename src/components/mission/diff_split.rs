@@ -0,0 +1,135 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ratatui::{
+    prelude::{Backend, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use similar::ChangeTag;
+
+use crate::{
+    app::AppState,
+    missions::ActionType,
+    utils::diff::{
+        action_original_content, action_proposed_content, split_diff_rows, SplitDiffCell,
+    },
+};
+
+use super::super::{AppComponent, DrawableComponent};
+
+/// Shows the original file and the proposed content of the selected action next to each other.
+pub struct DiffSplitComponent;
+
+impl DiffSplitComponent {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn as_mutex(self) -> Mutex<AppComponent> {
+        Mutex::new(AppComponent::DiffSplit(self))
+    }
+}
+
+impl DrawableComponent for DiffSplitComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        state: &mut AppState,
+        frame: &mut Frame<B>,
+        rect: Rect,
+    ) -> Result<()> {
+        let [left_rect, right_rect] = *Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
+            .split(rect)
+        else {
+            return Ok(());
+        };
+
+        let action = match state.action_items.get_selected_item() {
+            Some(item) => item.action.clone(),
+            None => {
+                let block = Block::default().borders(Borders::ALL).title("Side by Side");
+                frame.render_widget(block, rect);
+                return Ok(());
+            }
+        };
+        let original_content = action_original_content(&state.project_dir, &action);
+        let proposed_content = action_proposed_content(&state.project_dir, &action);
+        let rows = split_diff_rows(&original_content, &proposed_content);
+        state.preview_scroll = state
+            .preview_scroll
+            .min(rows.len().saturating_sub(1) as u16);
+
+        let gutter_width = rows.len().to_string().len();
+        let (old_lines, new_lines): (Vec<Line>, Vec<Line>) = rows
+            .iter()
+            .map(|row| {
+                (
+                    cell_to_line(row.old.as_ref(), gutter_width),
+                    cell_to_line(row.new.as_ref(), gutter_width),
+                )
+            })
+            .unzip();
+
+        let old_block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Original ( {} )", action.path));
+        let new_title = match action.action_type {
+            ActionType::DeleteFile => String::from("Proposed ( deleted )"),
+            _ => format!(
+                "Proposed ( {} )",
+                action.new_path.clone().unwrap_or(action.path.clone())
+            ),
+        };
+        let new_block = Block::default().borders(Borders::ALL).title(new_title);
+        let scroll = (state.preview_scroll, 0);
+        frame.render_widget(
+            Paragraph::new(old_lines).block(old_block).scroll(scroll),
+            left_rect,
+        );
+        frame.render_widget(
+            Paragraph::new(new_lines).block(new_block).scroll(scroll),
+            right_rect,
+        );
+
+        Ok(())
+    }
+}
+
+fn cell_to_line(cell: Option<&SplitDiffCell>, gutter_width: usize) -> Line<'static> {
+    let cell = match cell {
+        Some(cell) => cell,
+        None => return Line::from(""),
+    };
+    let (style, changed_style) = match cell.tag {
+        ChangeTag::Equal => (Style::default(), Style::default()),
+        ChangeTag::Delete => (
+            Style::default().fg(Color::Red),
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ),
+        ChangeTag::Insert => (
+            Style::default().fg(Color::Green),
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        ),
+    };
+    let mut spans = vec![Span::styled(
+        format!("{:>width$} ", cell.line_number, width = gutter_width),
+        Style::default().fg(Color::DarkGray),
+    )];
+    for (changed, text) in &cell.segments {
+        spans.push(Span::styled(
+            text.clone(),
+            if *changed { changed_style } else { style },
+        ));
+    }
+    Line::from(spans)
+}
@@ -1,5 +1,8 @@
 pub mod action_preview;
 pub mod actions;
 pub mod context_files;
+pub mod context_reduction;
+pub mod diff_split;
 pub mod message_input;
 pub mod project_info;
+pub mod verification;
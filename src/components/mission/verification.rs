@@ -0,0 +1,73 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ratatui::{
+    prelude::{Backend, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::{app::AppState, verification::VerificationStatus};
+
+use super::super::{AppComponent, DrawableComponent};
+
+/// Streams the output of the verify commands run after applying a mission.
+pub struct VerificationComponent;
+
+impl VerificationComponent {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn as_mutex(self) -> Mutex<AppComponent> {
+        Mutex::new(AppComponent::Verification(self))
+    }
+}
+
+impl DrawableComponent for VerificationComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        state: &mut AppState,
+        frame: &mut Frame<B>,
+        rect: Rect,
+    ) -> Result<()> {
+        let verification = match &state.verification {
+            Some(verification) => verification,
+            None => return Ok(()),
+        };
+        let color = match verification.status {
+            VerificationStatus::Confirming => Color::Yellow,
+            VerificationStatus::Running => Color::Cyan,
+            VerificationStatus::Passed => Color::Green,
+            VerificationStatus::Failed | VerificationStatus::RolledBack => Color::Red,
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Verification: {}", verification.status))
+            .border_style(Style::default().fg(color));
+
+        // the end of the output is shown, unless scrolled back
+        let height = rect.height.saturating_sub(2) as usize;
+        let end = verification
+            .output
+            .len()
+            .saturating_sub(verification.scroll_back);
+        let start = end.saturating_sub(height);
+        let lines: Vec<Line> = verification.output[start..end]
+            .iter()
+            .map(|line| match line.starts_with("$ ") {
+                true => Line::from(Span::styled(
+                    line.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+                false => Line::from(line.clone()),
+            })
+            .collect();
+
+        let widget = Paragraph::new(lines).block(block);
+        frame.render_widget(widget, rect);
+        Ok(())
+    }
+}
@@ -8,7 +8,9 @@ use ratatui::{
 
 pub mod button;
 pub mod header;
+pub mod history;
 pub mod mission;
+pub mod presets;
 pub mod shortcuts;
 pub mod text_input;
 
@@ -42,6 +44,13 @@ pub enum AppComponent {
     Shortcuts(shortcuts::ShortcutsComponent),
     TextInput(text_input::TextInputComponent),
     ActionPreview(mission::action_preview::ActionPreviewComponent),
+    DiffSplit(mission::diff_split::DiffSplitComponent),
+    HistoryList(history::history_list::HistoryListComponent),
+    HistoryDetails(history::history_details::HistoryDetailsComponent),
+    Verification(mission::verification::VerificationComponent),
+    ContextReduction(mission::context_reduction::ContextReductionComponent),
+    PresetList(presets::preset_list::PresetListComponent),
+    PresetDetails(presets::preset_details::PresetDetailsComponent),
 }
 
 impl AppComponent {
@@ -61,6 +70,13 @@ impl AppComponent {
             AppComponent::Shortcuts(component) => component.draw(state, frame, rect),
             AppComponent::TextInput(component) => component.draw(state, frame, rect),
             AppComponent::ActionPreview(component) => component.draw(state, frame, rect),
+            AppComponent::DiffSplit(component) => component.draw(state, frame, rect),
+            AppComponent::HistoryList(component) => component.draw(state, frame, rect),
+            AppComponent::HistoryDetails(component) => component.draw(state, frame, rect),
+            AppComponent::Verification(component) => component.draw(state, frame, rect),
+            AppComponent::ContextReduction(component) => component.draw(state, frame, rect),
+            AppComponent::PresetList(component) => component.draw(state, frame, rect),
+            AppComponent::PresetDetails(component) => component.draw(state, frame, rect),
         }
     }
 }
@@ -0,0 +1,2 @@
+pub mod preset_details;
+pub mod preset_list;
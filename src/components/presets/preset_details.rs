@@ -0,0 +1,87 @@
+use std::{path::Path, sync::Mutex};
+
+use anyhow::Result;
+use ratatui::{
+    prelude::{Backend, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    app::AppState, context_commands::item_command, utils::line_ranges::parse_context_item,
+};
+
+use super::super::{AppComponent, DrawableComponent};
+
+/// Shows the context items of the selected preset, files that no longer exist in red.
+pub struct PresetDetailsComponent;
+
+impl PresetDetailsComponent {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn as_mutex(self) -> Mutex<AppComponent> {
+        Mutex::new(AppComponent::PresetDetails(self))
+    }
+}
+
+impl DrawableComponent for PresetDetailsComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        state: &mut AppState,
+        frame: &mut Frame<B>,
+        rect: Rect,
+    ) -> Result<()> {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Preset")
+            .padding(Padding::new(1, 1, 0, 0));
+        let preset = match state.presets.get_selected_item() {
+            Some(preset) => preset,
+            None => {
+                let text = Paragraph::new(Span::styled(
+                    "No presets saved, press s in the context block to save one",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .block(block);
+                frame.render_widget(text, rect);
+                return Ok(());
+            }
+        };
+
+        let title_style = Style::default().add_modifier(Modifier::BOLD);
+        let mut lines = vec![
+            Line::from(Span::styled(preset.name.clone(), title_style)),
+            Line::from(Span::styled(
+                preset.scope.to_string(),
+                Style::default().fg(Color::DarkGray),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("Context items ({})", preset.items.len()),
+                title_style,
+            )),
+        ];
+        lines.extend(preset.items.iter().map(|item| {
+            let (path, _) = parse_context_item(item);
+            let exists =
+                item_command(item).is_some() || Path::new(&state.project_dir).join(path).exists();
+            match exists {
+                true => Line::from(item.clone()),
+                false => Line::from(Span::styled(
+                    format!("{} (missing)", item),
+                    Style::default().fg(Color::Red),
+                )),
+            }
+        }));
+
+        let widget = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(widget, rect);
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ratatui::{
+    prelude::{Backend, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, List, ListState},
+    Frame,
+};
+
+use crate::app::AppState;
+
+use super::super::{AppComponent, DrawableComponent};
+
+pub struct PresetListComponent;
+
+impl PresetListComponent {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn as_mutex(self) -> Mutex<AppComponent> {
+        Mutex::new(AppComponent::PresetList(self))
+    }
+}
+
+impl DrawableComponent for PresetListComponent {
+    fn draw<B: Backend>(
+        &mut self,
+        state: &mut AppState,
+        frame: &mut Frame<B>,
+        rect: Rect,
+    ) -> Result<()> {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Context Presets ({})", state.presets.len()))
+            .border_style(Style::default().fg(Color::Cyan));
+        let list = List::new(state.presets.to_items())
+            .block(block)
+            .highlight_style(Style::default().fg(Color::Yellow))
+            .highlight_symbol("> ");
+        let list_state = &mut ListState::default().with_selected(state.presets.selected_index);
+        frame.render_stateful_widget(list, rect, list_state);
+        Ok(())
+    }
+}
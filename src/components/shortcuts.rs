@@ -8,7 +8,11 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::{AppScreen, AppState, FocusedBlock};
+use crate::{
+    app::{AppScreen, AppState, FocusedBlock},
+    verification::VerificationStatus,
+    views::SearchableListType,
+};
 
 use super::{AppComponent, DrawableComponent};
 
@@ -23,7 +27,12 @@ impl ShortcutsComponent {
         Mutex::new(AppComponent::Shortcuts(self))
     }
 
-    fn get_shortcuts(&self, screen: &AppScreen, focused_block: &FocusedBlock) -> Vec<(&str, &str)> {
+    fn get_shortcuts(
+        &self,
+        screen: &AppScreen,
+        focused_block: &FocusedBlock,
+        list_type: &SearchableListType,
+    ) -> Vec<(&str, &str)> {
         match screen {
             AppScreen::SignIn => {
                 return vec![
@@ -40,6 +49,41 @@ impl ShortcutsComponent {
                     ("q", "quit"),
                 ];
             }
+            AppScreen::History => {
+                return vec![
+                    ("Esc", "back"),
+                    ("Up/Down", "select"),
+                    ("Enter", "load into mission"),
+                    ("Ctrl+r", "run again"),
+                ];
+            }
+            AppScreen::Presets => {
+                return vec![
+                    ("Esc", "back"),
+                    ("Up/Down", "select"),
+                    ("Enter", "load"),
+                    ("m", "merge into context"),
+                    ("d", "delete"),
+                ];
+            }
+            AppScreen::AddContextFiles => match list_type {
+                SearchableListType::ProjectFiles => {
+                    return vec![
+                        ("Esc", "back"),
+                        ("Up/Down", "select"),
+                        ("Space", "add file, directory or glob matches"),
+                    ];
+                }
+                SearchableListType::GitCommits => {
+                    return vec![
+                        ("Esc", "back"),
+                        ("Up/Down", "select"),
+                        ("Space", "mark"),
+                        ("Enter", "add files"),
+                        ("Tab", "add patches"),
+                    ];
+                }
+            },
             _ => {}
         }
 
@@ -54,6 +98,7 @@ impl ShortcutsComponent {
                     (".", "commit temp branch"),
                     ("q", "quit"),
                     ("u", "undo last commit"),
+                    ("H", "history"),
                     // ("r", "reset"),
                     // ("g", "git"),
                     // ("s", "settings"),
@@ -61,23 +106,90 @@ impl ShortcutsComponent {
                 ];
             }
             FocusedBlock::Message => return vec![("Esc", "exit"), ("Enter", "send")],
+            FocusedBlock::Feedback => {
+                return vec![("Esc", "back to actions"), ("Enter", "refine actions")]
+            }
             FocusedBlock::Actions => {
                 return vec![
                     ("y", "approve and run"),
+                    ("t", "toggle"),
+                    ("T", "toggle all"),
+                    ("h", "review hunks"),
+                    ("e", "edit"),
+                    ("f", "refine"),
+                    ("[/]", "revisions"),
                     ("x", "cancel"),
                     ("o", "open file"),
+                    ("v", "diff/content"),
+                    ("s", "side by side"),
+                    ("PgUp/PgDn", "scroll"),
                     ("Space", "add to context"),
                 ]
             }
+            FocusedBlock::ActionHunks => {
+                return vec![
+                    ("Esc", "back to actions"),
+                    ("t", "toggle hunk"),
+                    ("y", "accept"),
+                    ("n", "reject"),
+                ]
+            }
+            FocusedBlock::Conflicts => {
+                return vec![
+                    ("Esc", "back to actions"),
+                    ("o", "overwrite"),
+                    ("s", "skip action"),
+                    ("m", "merge"),
+                    ("y", "apply"),
+                ]
+            }
+            FocusedBlock::ContextRanges => {
+                return vec![
+                    ("Esc", "back to context"),
+                    ("Up/Down", "move"),
+                    ("Space", "start range"),
+                    ("Enter", "add lines"),
+                    ("d", "remove range"),
+                    ("w", "whole file"),
+                ]
+            }
+            FocusedBlock::ContextReduction => {
+                return vec![
+                    ("Esc", "back to message"),
+                    ("Enter", "send mission"),
+                    ("f", "full"),
+                    ("c", "no comments"),
+                    ("o", "outline"),
+                    ("m", "focus on message"),
+                ]
+            }
+            FocusedBlock::Verification => {
+                return vec![("Esc", "close"), ("Up/Down", "scroll"), ("r", "roll back")]
+            }
             FocusedBlock::ContextFiles => {
                 return vec![
                     ("p", "pick files"),
+                    ("g", "pick diffs/commits"),
                     ("d", "remove from context"),
                     ("x", "clear context"),
                     ("t", "copy actions"),
+                    ("r", "pick lines/refresh"),
+                    ("$", "add command output"),
+                    ("s", "save preset"),
+                    ("P", "presets"),
                     ("o", "open file"),
                 ]
             }
+            FocusedBlock::PresetNameInput => {
+                return vec![
+                    ("Esc", "back to context"),
+                    ("Enter", "save"),
+                    ("Tab", "save shared"),
+                ]
+            }
+            FocusedBlock::ContextCommandInput => {
+                return vec![("Esc", "back to context"), ("Enter", "run and add")]
+            }
             _ => {}
         }
 
@@ -100,7 +212,23 @@ impl DrawableComponent for ShortcutsComponent {
         //     .join("      ");
         let mut innerp: Vec<Span> = vec![];
 
-        self.get_shortcuts(&state.screen, &state.focused_block)
+        let mut shortcuts = self.get_shortcuts(
+            &state.screen,
+            &state.focused_block,
+            &state.searchable_list_type,
+        );
+        if state.mission_task.is_some() || state.deferred_mission.is_some() {
+            shortcuts.insert(0, ("Ctrl+c", "cancel mission"));
+        }
+        let confirming = matches!(
+            &state.verification,
+            Some(verification) if verification.status == VerificationStatus::Confirming
+        );
+        if state.focused_block == FocusedBlock::Verification && confirming {
+            shortcuts = vec![("y", "run the commands"), ("n", "skip verification")];
+        }
+
+        shortcuts
             .iter()
             .for_each(|(key, action)| {
                 innerp.push(Span::styled(
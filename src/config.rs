@@ -0,0 +1,138 @@
+use std::{path::PathBuf, sync::OnceLock};
+
+use anyhow::Result;
+use serde::Deserialize;
+
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Overrides the default `~/.lili` config dir, must be called before any setting is read.
+pub fn set_config_dir(config_dir: PathBuf) {
+    CONFIG_DIR.set(config_dir).ok();
+}
+
+pub fn config_dir() -> PathBuf {
+    CONFIG_DIR
+        .get_or_init(|| {
+            let home = std::env::var("HOME").unwrap_or_default();
+            PathBuf::from(home).join(".lili")
+        })
+        .clone()
+}
+
+/// Turns the project dir into a file name, for the files lili keeps per project.
+pub fn project_file_name(project_dir: &str) -> String {
+    project_dir
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+// lili settings and the auth tokens live in `config.json`
+fn config_path() -> PathBuf {
+    config_dir().join("config.json")
+}
+
+fn read_config() -> serde_json::Value {
+    match std::fs::read_to_string(config_path()) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or(serde_json::json!({})),
+        Err(_) => serde_json::json!({}),
+    }
+}
+
+fn write_config(content: &serde_json::Value) -> Result<()> {
+    std::fs::create_dir_all(config_dir())?;
+    std::fs::write(config_path(), serde_json::to_string_pretty(content)?)?;
+    Ok(())
+}
+
+// lili used to keep its settings and tokens in lilicore's config file
+const LEGACY_CONFIG_PATH: &str = "/home/l/.lili/config.json";
+const LEGACY_MIGRATED_KEY: &str = "legacy_config_migrated";
+
+/// Copies the settings of the legacy config file, like the auth tokens and the base branches,
+/// to the default config dir once. Settings already in the config dir are kept.
+pub fn migrate_legacy_config() -> Result<()> {
+    let mut content = read_config();
+    if content.get(LEGACY_MIGRATED_KEY).is_some() {
+        return Ok(());
+    }
+    let legacy_content = match std::fs::read_to_string(LEGACY_CONFIG_PATH) {
+        Ok(legacy_content) => legacy_content,
+        Err(_) => return Ok(()),
+    };
+    let legacy: serde_json::Value = serde_json::from_str(&legacy_content)?;
+    if let (Some(object), Some(legacy)) = (content.as_object_mut(), legacy.as_object()) {
+        for (key, value) in legacy {
+            // lilicore deletes keys by setting them to null
+            if !value.is_null() && !object.contains_key(key) {
+                object.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    content[LEGACY_MIGRATED_KEY] = serde_json::Value::Bool(true);
+    write_config(&content)
+}
+
+pub fn get(key: &str) -> Option<String> {
+    let content = read_config();
+    let value = content.get(key)?.as_str()?;
+    Some(value.to_string())
+}
+
+pub fn set(key: &str, value: &str) -> Result<()> {
+    let mut content = read_config();
+    content[key] = serde_json::Value::String(value.to_string());
+    write_config(&content)
+}
+
+pub fn delete(key: &str) -> Result<()> {
+    let mut content = read_config();
+    if let Some(object) = content.as_object_mut() {
+        object.remove(key);
+    }
+    write_config(&content)
+}
+
+/// Settings shared with everyone working on the project, read from `<project>/.lili/config.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    // run one after the other once the actions are applied, e.g. `cargo check`
+    pub verify_commands: Vec<String>,
+    // undo the commit and fail the execution without asking when verification fails
+    pub rollback_on_failure: bool,
+}
+
+impl ProjectConfig {
+    /// Whether the user agreed to run these verify commands, they come from the repository
+    /// so anyone who can commit to it could change them.
+    pub fn verify_commands_trusted(&self, project_dir: &str) -> bool {
+        get(&trusted_verify_commands_key(project_dir))
+            == serde_json::to_string(&self.verify_commands).ok()
+    }
+
+    /// Remembers that the verify commands can run, until they change.
+    pub fn trust_verify_commands(&self, project_dir: &str) -> Result<()> {
+        set(
+            &trusted_verify_commands_key(project_dir),
+            &serde_json::to_string(&self.verify_commands)?,
+        )
+    }
+}
+
+// the commands themselves are kept, so any change to them asks again
+fn trusted_verify_commands_key(project_dir: &str) -> String {
+    format!("trusted_verify_commands_{}", project_file_name(project_dir))
+}
+
+/// Loads the project settings, the defaults are used when the project has none.
+pub fn load_project_config(project_dir: &str) -> Result<ProjectConfig> {
+    let path = PathBuf::from(project_dir).join(".lili").join("config.json");
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return Ok(ProjectConfig::default()),
+    };
+    serde_json::from_str(&content)
+        .map_err(|err| anyhow::anyhow!("Invalid {}: {}", path.display(), err))
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use anyhow::Result;
+use strum::Display;
+
+use crate::{
+    missions::{ActionItem, ActionType},
+    utils::diff::{action_proposed_content, merge_hunks, three_way_merge},
+};
+
+/// A file as it was when the actions were proposed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSnapshot {
+    pub hash: u64,
+    // the base of three-way merges
+    pub content: String,
+}
+
+/// Snapshots by path, None for files that did not exist.
+pub type ProjectSnapshot = HashMap<String, Option<FileSnapshot>>;
+
+pub fn take_snapshot(project_dir: &str, paths: &[String]) -> ProjectSnapshot {
+    paths
+        .iter()
+        .map(|path| (path.clone(), read_snapshot(project_dir, path)))
+        .collect()
+}
+
+fn read_snapshot(project_dir: &str, path: &str) -> Option<FileSnapshot> {
+    let content = std::fs::read_to_string(format!("{}/{}", project_dir, path)).ok()?;
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    Some(FileSnapshot {
+        hash: hasher.finish(),
+        content,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum ConflictResolution {
+    Overwrite,
+    Skip,
+    Merge,
+}
+
+/// A file that changed on disk after the actions were proposed.
+#[derive(Debug, Clone)]
+pub struct FileConflict {
+    pub path: String,
+    // index of the checked action writing the file, None for context files
+    pub action_index: Option<usize>,
+    pub resolution: ConflictResolution,
+}
+
+/// Returns the files of the snapshot that changed since it was taken.
+///
+/// Files only written by unchecked actions are left out, they will not be touched.
+pub fn find_conflicts(
+    project_dir: &str,
+    snapshot: &ProjectSnapshot,
+    action_items: &[ActionItem],
+) -> Vec<FileConflict> {
+    let mut conflicts: Vec<FileConflict> = snapshot
+        .iter()
+        .filter(|(path, file)| {
+            let hash = |file: Option<&FileSnapshot>| file.map(|file| file.hash);
+            hash(read_snapshot(project_dir, path).as_ref()) != hash(file.as_ref())
+        })
+        .filter_map(|(path, _)| {
+            let written_by = action_items
+                .iter()
+                .position(|item| item.action.paths().contains(path));
+            let action_index = match written_by {
+                Some(index) if !action_items[index].checked => return None,
+                action_index => action_index,
+            };
+            Some(FileConflict {
+                path: path.clone(),
+                action_index,
+                // nothing is lost until the user picks another resolution
+                resolution: match action_index {
+                    Some(_) => ConflictResolution::Skip,
+                    None => ConflictResolution::Overwrite,
+                },
+            })
+        })
+        .collect();
+    conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+    conflicts
+}
+
+/// Unchecks the skipped actions and merges the changes on disk into the merged ones.
+pub fn resolve_conflicts(
+    project_dir: &str,
+    snapshot: &ProjectSnapshot,
+    conflicts: &[FileConflict],
+    action_items: &mut [ActionItem],
+) -> Result<()> {
+    for conflict in conflicts {
+        let item = match conflict.action_index {
+            Some(index) => &mut action_items[index],
+            None => continue,
+        };
+        match conflict.resolution {
+            ConflictResolution::Overwrite => {}
+            ConflictResolution::Skip => item.checked = false,
+            ConflictResolution::Merge => {
+                let base = match snapshot.get(&conflict.path) {
+                    Some(Some(file)) => file.content.clone(),
+                    _ => String::new(),
+                };
+                let current = std::fs::read_to_string(format!("{}/{}", project_dir, conflict.path))
+                    .unwrap_or_default();
+                // hunks were reviewed against the file as it was when proposed
+                let proposed = match merge_hunks(
+                    &base,
+                    &action_proposed_content(project_dir, &item.action),
+                    &item.rejected_hunks,
+                ) {
+                    Some(proposed) => proposed,
+                    None => anyhow::bail!(
+                        "{} can not be merged, review its rejected hunks again",
+                        conflict.path
+                    ),
+                };
+                match three_way_merge(&base, &current, &proposed) {
+                    Some(merged) => {
+                        item.action.content = Some(merged);
+                        item.rejected_hunks.clear();
+                    }
+                    None => anyhow::bail!(
+                        "{} can not be merged, the same lines changed on disk",
+                        conflict.path
+                    ),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the reason a resolution can not be used for the conflict, if any.
+pub fn unsupported_resolution(
+    conflict: &FileConflict,
+    resolution: ConflictResolution,
+    action_items: &[ActionItem],
+) -> Option<&'static str> {
+    let action_type = match conflict.action_index {
+        Some(index) => action_items[index].action.action_type,
+        None if resolution == ConflictResolution::Overwrite => return None,
+        None => return Some("Context files are only read, there is nothing to skip or merge"),
+    };
+    match (action_type, resolution) {
+        (ActionType::DeleteFile, ConflictResolution::Merge) => {
+            Some("Deleted files can not be merged")
+        }
+        _ => None,
+    }
+}
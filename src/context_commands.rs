@@ -0,0 +1,83 @@
+use std::process::Stdio;
+
+use anyhow::Result;
+use tokio::{process::Command, sync::mpsc::UnboundedSender};
+
+use crate::events::AppEvent;
+
+const COMMAND_PREFIX: &str = "$ ";
+
+/// Returns the command of a context item made of command output, like `$ git diff`.
+///
+/// The output of these items is kept in the second field of the context tuple.
+pub fn item_command(item: &str) -> Option<&str> {
+    item.strip_prefix(COMMAND_PREFIX)
+}
+
+pub fn command_item(command: &str) -> String {
+    format!("{}{}", COMMAND_PREFIX, command.trim())
+}
+
+/// Returns the items of the unstaged and staged changes, and of everything committed on the
+/// temporary branch when its base branch is known.
+pub fn git_diff_items(base_branch_name: Option<String>) -> Vec<String> {
+    let mut items = vec![command_item("git diff"), command_item("git diff --staged")];
+    if let Some(base_branch_name) = base_branch_name {
+        items.push(command_item(&format!(
+            "git diff {}...HEAD",
+            shell_quote(&base_branch_name)
+        )));
+    }
+    items
+}
+
+// single quotes the argument for `sh -c`, unless it only has characters the shell leaves alone
+fn shell_quote(arg: &str) -> String {
+    let is_plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./+@".contains(c));
+    match is_plain {
+        true => arg.to_string(),
+        false => format!("'{}'", arg.replace('\'', "'\\''")),
+    }
+}
+
+/// Runs the command with `sh -c` in the project dir and returns what it printed.
+///
+/// lilicore's `run_shell_command` splits the command on whitespace, so it can not run
+/// pipes or redirections like `cargo test 2>&1`.
+pub async fn run_context_command(project_dir: &str, command: &str) -> Result<String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(project_dir)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    // the output of a failing command is often what the mission is about
+    if !output.status.success() {
+        text.push_str(&format!("\n({})", output.status));
+    }
+    Ok(text)
+}
+
+/// Runs the command of the item in the background, the output comes back as
+/// `AppEvent::ContextCommand`.
+pub fn spawn_context_command(project_dir: &str, item: &str, sender: UnboundedSender<AppEvent>) {
+    let command = match item_command(item) {
+        Some(command) => command.to_string(),
+        None => return,
+    };
+    let project_dir = project_dir.to_string();
+    let item = item.to_string();
+    tokio::spawn(async move {
+        let output = match run_context_command(&project_dir, &command).await {
+            Ok(output) => output,
+            Err(err) => format!("Failed to run the command: {}", err),
+        };
+        sender.send(AppEvent::ContextCommand(item, output)).ok();
+    });
+}
@@ -0,0 +1,107 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyEvent};
+use lilicore::code_missions_api::CreateMissionResponse;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::{conflicts::ProjectSnapshot, missions::Execution};
+
+pub enum AppEvent {
+    Key(KeyEvent),
+    Tick,
+    // the usize is the id of the MissionTask that sent the event
+    Mission(usize, MissionEvent),
+    Verification(VerificationEvent),
+    // a context item and the output of its command
+    ContextCommand(String, String),
+}
+
+pub enum MissionEvent {
+    Phase(String),
+    ContextGenerated(CreateMissionResponse),
+    // with the context files and action targets as they were when the mission read them
+    Executed(Execution, ProjectSnapshot),
+    Failed(String),
+}
+
+pub enum VerificationEvent {
+    Output(String),
+    // true when every command succeeded
+    Finished(bool),
+}
+
+pub struct EventHandler {
+    sender: UnboundedSender<AppEvent>,
+    receiver: UnboundedReceiver<AppEvent>,
+    // set while an external program owns the terminal
+    paused: Arc<AtomicBool>,
+}
+
+impl EventHandler {
+    pub fn new(tick_rate: Duration) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let input_sender = sender.clone();
+        let paused = Arc::new(AtomicBool::new(false));
+        let input_paused = paused.clone();
+        // crossterm only offers a blocking api, so terminal input is read in its own thread
+        thread::spawn(move || {
+            let mut last_tick = Instant::now();
+            loop {
+                if input_paused.load(Ordering::SeqCst) {
+                    thread::sleep(tick_rate);
+                    continue;
+                }
+                let timeout = tick_rate
+                    .checked_sub(last_tick.elapsed())
+                    .unwrap_or(Duration::ZERO);
+                // the check after poll keeps the key pressed right after pausing for the editor
+                if event::poll(timeout).unwrap_or(false) && !input_paused.load(Ordering::SeqCst) {
+                    if let Ok(Event::Key(key)) = event::read() {
+                        if input_sender.send(AppEvent::Key(key)).is_err() {
+                            break;
+                        }
+                    }
+                }
+                if last_tick.elapsed() >= tick_rate {
+                    if input_sender.send(AppEvent::Tick).is_err() {
+                        break;
+                    }
+                    last_tick = Instant::now();
+                }
+            }
+        });
+        Self {
+            sender,
+            receiver,
+            paused,
+        }
+    }
+
+    /// Stops reading terminal input, so it is not taken away from an external program.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn sender(&self) -> UnboundedSender<AppEvent> {
+        self.sender.clone()
+    }
+
+    pub async fn next(&mut self) -> Result<AppEvent> {
+        match self.receiver.recv().await {
+            Some(event) => Ok(event),
+            None => anyhow::bail!("Event channel closed"),
+        }
+    }
+}
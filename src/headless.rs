@@ -0,0 +1,226 @@
+use std::io::IsTerminal;
+
+use anyhow::Result;
+use clap::Args;
+use lilicore::{
+    code_missions_api::{CodeMissionStatus, CreateMissionResponse, SetApprovedRequest},
+    git_repo::get_current_branch_name,
+};
+use serde_json::json;
+
+use crate::{
+    backend::MissionBackend,
+    config::{load_project_config, ProjectConfig},
+    history::{append_history_entry, HistoryEntry, MissionOutcome},
+    missions::{apply_actions, build_mission_data, execute_mission, generate_context_files},
+    utils::git::git_add_temporary_commit_paths,
+    verification::{rollback_mission, run_verify_commands},
+};
+
+/// Options of `lili run`, the scripted counterpart of the Mission screen.
+#[derive(Debug, Args)]
+pub struct RunOptions {
+    /// Message describing the mission
+    #[arg(short, long)]
+    pub message: String,
+
+    /// Context file, can be repeated. When missing the context files are generated
+    #[arg(short, long = "context", value_name = "PATH")]
+    pub context_files: Vec<String>,
+
+    /// Apply the actions and commit them
+    #[arg(long)]
+    pub apply: bool,
+
+    /// Print the actions as json
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Runs a mission without the terminal ui and prints the proposed actions.
+///
+/// Progress goes to stderr so `--json` keeps stdout parseable. Like the Mission screen,
+/// `--apply` only commits on a temporary branch unless `no_temp_branch` is set.
+pub async fn run(
+    backend: &dyn MissionBackend,
+    project_dir: &str,
+    options: RunOptions,
+    no_temp_branch: bool,
+) -> Result<()> {
+    if options.message.trim().is_empty() {
+        anyhow::bail!("Message cannot be empty");
+    }
+    if options.apply && !no_temp_branch {
+        let current_branch_name = get_current_branch_name(project_dir)?;
+        if !current_branch_name.starts_with("temp-") {
+            anyhow::bail!(
+                "--apply would commit on {}, check out a temporary branch first or pass --no-temp-branch",
+                current_branch_name.trim()
+            );
+        }
+    }
+    let mission_data = build_mission_data(project_dir, &options.message);
+    let res_ctx = match options.context_files.is_empty() {
+        true => {
+            eprintln!("Generating context files...");
+            match generate_context_files(backend, &mission_data).await {
+                Ok(res_ctx) => res_ctx,
+                Err(err) => anyhow::bail!(err.message),
+            }
+        }
+        false => CreateMissionResponse {
+            mission_id: String::from(""),
+            context_files: options
+                .context_files
+                .iter()
+                .map(|path| path.trim_start_matches("./").to_string())
+                .collect(),
+            mission_status: CodeMissionStatus::Created,
+        },
+    };
+    let context_files = res_ctx.context_files.clone();
+    eprintln!("Executing mission...");
+    let execution = match execute_mission(backend, mission_data, res_ctx, vec![]).await {
+        Ok(execution) => execution,
+        Err(err) => anyhow::bail!(err.message),
+    };
+    let actions = execution.actions;
+
+    let mut history_entry = HistoryEntry::new(
+        &options.message,
+        context_files.clone(),
+        Some(execution.execution_id.clone()),
+        actions.clone(),
+        MissionOutcome::Proposed,
+    );
+    if options.apply && !actions.is_empty() {
+        // read before applying, a broken config should not leave an unverified commit behind
+        let project_config = load_project_config(project_dir)?;
+        let verify = confirm_verify_commands(project_dir, &project_config)?;
+        eprintln!("Applying {} actions...", actions.len());
+        let req_approved = SetApprovedRequest {
+            execution_id: execution.execution_id.clone(),
+        };
+        if let Err(err) = backend.set_approved(req_approved).await {
+            anyhow::bail!(err.message);
+        }
+        apply_actions(project_dir, &actions)?;
+        let paths: Vec<String> = actions.iter().flat_map(|action| action.paths()).collect();
+        let commit_hash = git_add_temporary_commit_paths(
+            project_dir,
+            &paths,
+            Some(execution.execution_id.clone()),
+        )?;
+        history_entry.outcome = MissionOutcome::Applied;
+        match &commit_hash {
+            Some(commit_hash) => {
+                history_entry = history_entry.with_commit_hash(commit_hash.clone())
+            }
+            None => eprintln!("The actions left the files unchanged, there was nothing to commit"),
+        }
+        // without a commit there is nothing to verify or roll back
+        let verified_commit = commit_hash.filter(|_| verify);
+        if let Some(commit_hash) = verified_commit {
+            eprintln!("Verifying the mission...");
+            let passed =
+                run_verify_commands(project_dir, &project_config.verify_commands, |line| {
+                    eprintln!("{}", line)
+                })
+                .await;
+            history_entry.outcome = match passed {
+                true => MissionOutcome::Verified,
+                false => MissionOutcome::Broken,
+            };
+            if !passed && project_config.rollback_on_failure {
+                eprintln!("Verification failed, rolling the mission back...");
+                match rollback_mission(backend, project_dir, &execution.execution_id, &commit_hash)
+                    .await
+                {
+                    Ok(_) => history_entry.outcome = MissionOutcome::RolledBack,
+                    Err(err) => eprintln!("Failed to roll the mission back: {}", err),
+                }
+            }
+        }
+    }
+    if let Err(err) = append_history_entry(project_dir, &history_entry) {
+        eprintln!("Failed to save the mission history: {}", err);
+    }
+
+    if options.json {
+        let document = json!({
+            "execution_id": execution.execution_id,
+            "context_files": context_files,
+            "actions": actions,
+            "applied": options.apply,
+            "outcome": history_entry.outcome.to_string(),
+        });
+        println!("{}", serde_json::to_string_pretty(&document)?);
+        return verification_result(history_entry.outcome);
+    }
+    for action in &actions {
+        match &action.new_path {
+            Some(new_path) => println!("{} {} {}", action.action_type, action.path, new_path),
+            None => println!("{} {}", action.action_type, action.path),
+        }
+        if let Some(content) = action
+            .content
+            .as_deref()
+            .filter(|content| !content.is_empty())
+        {
+            println!("{}", content);
+        }
+        println!();
+    }
+    match options.apply {
+        true if history_entry.commit_hash.is_none() => {
+            eprintln!("Applied {} actions, nothing changed", actions.len())
+        }
+        true => eprintln!("Applied and committed {} actions", actions.len()),
+        false => eprintln!(
+            "{} actions proposed, use --apply to apply them",
+            actions.len()
+        ),
+    }
+    verification_result(history_entry.outcome)
+}
+
+// the verify commands come from the repository, they only run once the user agreed to them
+fn confirm_verify_commands(project_dir: &str, project_config: &ProjectConfig) -> Result<bool> {
+    if project_config.verify_commands.is_empty() {
+        return Ok(false);
+    }
+    if project_config.verify_commands_trusted(project_dir) {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        eprintln!(
+            "Skipping verification, the verify commands of the project must be confirmed once \
+            from a terminal"
+        );
+        return Ok(false);
+    }
+    eprintln!("The project verifies missions with these commands:");
+    for command in &project_config.verify_commands {
+        eprintln!("$ {}", command);
+    }
+    eprint!("Run them? (y/n) ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        eprintln!("Skipping verification");
+        return Ok(false);
+    }
+    project_config.trust_verify_commands(project_dir)?;
+    Ok(true)
+}
+
+// scripts get a failing exit code when the applied mission did not pass verification
+fn verification_result(outcome: MissionOutcome) -> Result<()> {
+    match outcome {
+        MissionOutcome::Broken => anyhow::bail!("Verification failed, the commit was kept"),
+        MissionOutcome::RolledBack => {
+            anyhow::bail!("Verification failed, the mission was rolled back")
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use crate::{config, missions::ProposedAction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Display)]
+pub enum MissionOutcome {
+    Applied,
+    Rejected,
+    Failed,
+    // executed by `lili run` without --apply
+    Proposed,
+    // applied and the verify commands of the project passed
+    Verified,
+    // applied, but a verify command failed
+    Broken,
+    // a verify command failed and the commit was undone
+    RolledBack,
+}
+
+/// A finished mission, as shown in the History screen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    // seconds since the unix epoch
+    pub timestamp: u64,
+    pub message: String,
+    pub context_paths: Vec<String>,
+    pub execution_id: Option<String>,
+    pub actions: Vec<ProposedAction>,
+    pub outcome: MissionOutcome,
+    // the temporary commit of applied missions
+    pub commit_hash: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(
+        message: &str,
+        context_paths: Vec<String>,
+        execution_id: Option<String>,
+        actions: Vec<ProposedAction>,
+        outcome: MissionOutcome,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            timestamp,
+            message: message.to_string(),
+            context_paths,
+            execution_id,
+            actions,
+            outcome,
+            commit_hash: None,
+        }
+    }
+
+    pub fn with_commit_hash(mut self, commit_hash: String) -> Self {
+        self.commit_hash = Some(commit_hash);
+        self
+    }
+
+    /// Returns true when the message or any of the paths contain the query, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.message.to_lowercase().contains(&query)
+            || self
+                .context_paths
+                .iter()
+                .chain(self.actions.iter().map(|action| &action.path))
+                .any(|path| path.to_lowercase().contains(&query))
+    }
+}
+
+/// Formats the age of the entry, e.g. `5m ago`.
+pub fn format_age(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let seconds = now.saturating_sub(timestamp);
+    match seconds {
+        0..=59 => String::from("just now"),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+// one json line per mission, every project has its own file
+fn history_path(project_dir: &str) -> PathBuf {
+    config::config_dir()
+        .join("history")
+        .join(format!("{}.jsonl", config::project_file_name(project_dir)))
+}
+
+pub fn append_history_entry(project_dir: &str, entry: &HistoryEntry) -> Result<()> {
+    let path = history_path(project_dir);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Changes the outcome of the mission that made the commit, once its verification finished.
+pub fn set_history_outcome(
+    project_dir: &str,
+    commit_hash: &str,
+    outcome: MissionOutcome,
+) -> Result<()> {
+    let path = history_path(project_dir);
+    let content = std::fs::read_to_string(&path)?;
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| match serde_json::from_str::<HistoryEntry>(line) {
+            Ok(mut entry) if entry.commit_hash.as_deref() == Some(commit_hash) => {
+                entry.outcome = outcome;
+                serde_json::to_string(&entry).unwrap_or(line.to_string())
+            }
+            // lines of other missions are kept as they are
+            _ => line.to_string(),
+        })
+        .collect();
+    std::fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Returns the missions of the project, newest first. Lines that can not be parsed are skipped.
+pub fn load_history(project_dir: &str) -> Vec<HistoryEntry> {
+    let content = std::fs::read_to_string(history_path(project_dir)).unwrap_or_default();
+    let mut entries: Vec<HistoryEntry> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    entries.reverse();
+    entries
+}
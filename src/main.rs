@@ -1,22 +1,66 @@
 mod app;
+mod backend;
+mod cli;
 mod components;
+mod config;
+mod conflicts;
+mod context_commands;
+mod events;
+mod headless;
+mod history;
+mod missions;
+mod presets;
+mod reduction;
 mod shortcuts;
 mod utils;
+mod verification;
 mod views;
 
-use std::{error::Error, io, sync::Mutex};
+use std::{error::Error, io, sync::Mutex, time::Duration};
 
 use anyhow::Result;
-use app::{App, AppState};
+use app::{App, AppState, StartOptions};
+use backend::backend_from_settings;
+use clap::Parser;
+use cli::{Cli, Command};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use events::EventHandler;
 use ratatui::{prelude::*, widgets::*};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    match &cli.config_dir {
+        Some(config_dir) => config::set_config_dir(config_dir.clone()),
+        // another config dir is a separate profile, it starts without the legacy settings
+        None => {
+            if let Err(err) = config::migrate_legacy_config() {
+                eprintln!("Failed to migrate the legacy config file: {}", err);
+            }
+        }
+    }
+    // fail before the terminal enters raw mode, so the error stays readable
+    let project_dir = cli.resolve_project_dir()?;
+    let backend = backend_from_settings()?;
+
+    if let Some(Command::Run(options)) = cli.command {
+        headless::run(backend.as_ref(), &project_dir, options, cli.no_temp_branch).await?;
+        return Ok(());
+    }
+
+    let options = StartOptions {
+        no_temp_branch: cli.no_temp_branch,
+        base_branch: cli.base_branch.clone(),
+        screen: cli.screen.clone(),
+    };
+    let mut events = EventHandler::new(Duration::from_millis(200));
+    let state = Mutex::new(AppState::new(project_dir, backend, events.sender(), options).await?);
+    let mut app = App::new(state)?;
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -24,17 +68,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // if the cli was called with an argument, then use that as the project_dir
-    let project_dir = match std::env::args().nth(1) {
-        Some(arg) => arg,
-        // otherwise the project_dir is the current dir that user called this program
-        None => std::env::current_dir()?.to_str().unwrap().to_string(),
-    };
-    let project_dir = project_dir.trim_end_matches('/').to_string();
-    // create app and run it
-    let state = Mutex::new(AppState::new(project_dir).await?);
-    let mut app = App::new(state)?;
-    let res = run_app(&mut terminal, &mut app).await;
+    // run app
+    let res = run_app(&mut terminal, &mut app, &mut events).await;
 
     // restore terminal
     disable_raw_mode()?;
@@ -60,23 +95,25 @@ fn draw_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
     Ok(())
 }
 
-pub fn redraw_app(state: &mut AppState) {
-    let state = Mutex::new(state.clone());
-    let mut app = App::new(state).unwrap();
-    let stdout = io::stdout();
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend).unwrap();
-    terminal.clear();
-    draw_app(&mut terminal, &mut app).unwrap();
-}
-
-async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+async fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    events: &mut EventHandler,
+) -> Result<()> {
     loop {
         draw_app(terminal, app)?;
 
-        match app.handle_events().await? {
-            true => return Ok(()),
-            false => continue,
+        let event = events.next().await?;
+        if app.handle_event(event).await? {
+            return Ok(());
+        }
+
+        if app.take_edit_action_request() {
+            events.pause();
+            app.edit_selected_action();
+            events.resume();
+            // the editor drew over the screen ratatui thinks is still there
+            terminal.clear()?;
         }
     }
 }
@@ -0,0 +1,509 @@
+use std::{
+    collections::HashSet,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use anyhow::Result;
+use lilicore::{
+    code_analyst,
+    code_missions_api::{
+        ApiError, CodeMissionStatus, CreateMissionRequest, CreateMissionResponse,
+        ExecuteMissionRequest, MissionAction, MissionActionType, MissionData, MissionExecution,
+        MissionExecutionContextFile,
+    },
+    io::LocalPath,
+};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use tokio::{
+    sync::mpsc::UnboundedSender,
+    task::{AbortHandle, JoinHandle},
+};
+
+use crate::{
+    backend::MissionBackend,
+    conflicts::{take_snapshot, ProjectSnapshot},
+    context_commands::item_command,
+    events::{AppEvent, MissionEvent},
+    utils::{
+        diff::{action_original_content, action_proposed_content, merge_hunks, HunkChange},
+        line_ranges::{parse_context_item, read_context_item},
+        list::SelectableList,
+        llm_tokens::TokenCounter,
+    },
+};
+
+// variant names match the ones used by the mission api
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Display)]
+pub enum ActionType {
+    CreateFile,
+    UpdateFile,
+    DeleteFile,
+    RenameFile,
+}
+
+/// A file change proposed by a mission.
+///
+/// lilicore's `MissionAction` can only create and update files, so the backends convert
+/// their actions to this type, which can also delete and rename them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposedAction {
+    pub action_type: ActionType,
+    pub path: String,
+    // None for deletes, and for renames that keep the content of the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    // destination of RenameFile actions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_path: Option<String>,
+}
+
+impl From<MissionAction> for ProposedAction {
+    fn from(action: MissionAction) -> Self {
+        Self {
+            action_type: match action.action_type {
+                MissionActionType::CreateFile => ActionType::CreateFile,
+                MissionActionType::UpdateFile => ActionType::UpdateFile,
+            },
+            path: action.path,
+            content: Some(action.content),
+            new_path: None,
+        }
+    }
+}
+
+impl ProposedAction {
+    /// Returns the action as lilicore knows it, None for deletes and renames.
+    pub fn to_mission_action(&self) -> Option<MissionAction> {
+        let action_type = match self.action_type {
+            ActionType::CreateFile => MissionActionType::CreateFile,
+            ActionType::UpdateFile => MissionActionType::UpdateFile,
+            ActionType::DeleteFile | ActionType::RenameFile => return None,
+        };
+        Some(MissionAction {
+            action_type,
+            path: self.path.clone(),
+            content: self.content.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Returns every path the action changes, a rename changes both the old and the new one.
+    pub fn paths(&self) -> Vec<String> {
+        match &self.new_path {
+            Some(new_path) => vec![self.path.clone(), new_path.clone()],
+            None => vec![self.path.clone()],
+        }
+    }
+}
+
+/// The result of executing a mission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Execution {
+    pub execution_id: String,
+    #[serde(alias = "original_actions")]
+    pub actions: Vec<ProposedAction>,
+}
+
+impl From<MissionExecution> for Execution {
+    fn from(execution: MissionExecution) -> Self {
+        Self {
+            execution_id: execution.execution_id,
+            actions: execution
+                .original_actions
+                .into_iter()
+                .map(ProposedAction::from)
+                .collect(),
+        }
+    }
+}
+
+/// An action proposed by the mission, only the checked ones are applied.
+#[derive(Debug, Clone)]
+pub struct ActionItem {
+    pub action: ProposedAction,
+    pub checked: bool,
+    // the diff hunks that keep the original content
+    pub rejected_hunks: HashSet<HunkChange>,
+}
+
+impl ActionItem {
+    pub fn new(action: ProposedAction) -> Self {
+        Self {
+            action,
+            checked: true,
+            rejected_hunks: HashSet::new(),
+        }
+    }
+
+    pub fn toggle_hunk(&mut self, change: HunkChange) {
+        if !self.rejected_hunks.remove(&change) {
+            self.rejected_hunks.insert(change);
+        }
+    }
+
+    /// Returns the action with the rejected hunks reverted to the file on disk.
+    ///
+    /// Fails when the file changed on disk so that a rejected hunk is not in its diff anymore.
+    pub fn reviewed_action(&self, project_dir: &str) -> Result<ProposedAction> {
+        if self.rejected_hunks.is_empty() {
+            return Ok(self.action.clone());
+        }
+        let original_content = action_original_content(project_dir, &self.action);
+        let proposed_content = action_proposed_content(project_dir, &self.action);
+        match merge_hunks(&original_content, &proposed_content, &self.rejected_hunks) {
+            Some(content) => Ok(ProposedAction {
+                content: Some(content),
+                ..self.action.clone()
+            }),
+            None => anyhow::bail!(
+                "{} changed on disk, review its rejected hunks again",
+                self.action.path
+            ),
+        }
+    }
+}
+
+/// One set of actions proposed for the mission, every refinement adds a revision.
+#[derive(Debug, Clone)]
+pub struct ActionRevision {
+    pub execution_id: String,
+    // the feedback that produced the revision, None for the first one
+    pub feedback: Option<String>,
+    pub action_items: SelectableList<ActionItem>,
+    // context files and action targets when the actions were proposed
+    pub snapshot: ProjectSnapshot,
+}
+
+/// Feedback on the proposed actions, sent with them to get a new revision.
+#[derive(Debug, Clone)]
+pub struct Refinement {
+    pub feedback: String,
+    pub previous_actions: Vec<ProposedAction>,
+}
+
+impl Refinement {
+    // the feedback is sent after the message of the mission
+    fn message(&self, message: &str) -> String {
+        format!(
+            "{}\n\nFeedback on the previous proposal: {}",
+            message, self.feedback
+        )
+    }
+
+    // the previous actions are sent as extra context files, next to the real ones
+    fn context_files(&self) -> Vec<MissionExecutionContextFile> {
+        self.previous_actions
+            .iter()
+            .map(|action| {
+                let content = match action.action_type {
+                    ActionType::DeleteFile => String::from("(deleted)"),
+                    ActionType::RenameFile => format!(
+                        "(moved to {})\n{}",
+                        action.new_path.clone().unwrap_or_default(),
+                        action.content.as_deref().unwrap_or("(unchanged)")
+                    ),
+                    _ => action.content.clone().unwrap_or_default(),
+                };
+                MissionExecutionContextFile {
+                    path: format!("{} (previous proposal)", action.path),
+                    content,
+                }
+            })
+            .collect()
+    }
+
+    /// Tokens the feedback and the previous actions add to the mission.
+    pub fn tokens(&self, counter: &TokenCounter) -> usize {
+        let context_tokens: usize = self
+            .context_files()
+            .iter()
+            .map(|file| counter.count(&file.content))
+            .sum();
+        counter.count(&self.message("")) + context_tokens
+    }
+}
+
+static NEXT_MISSION_TASK_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Debug, Clone)]
+pub struct MissionTask {
+    pub id: usize,
+    pub phase: String,
+    pub phase_started_at: Instant,
+    pub abort_handle: Arc<AbortHandle>,
+    // restored when the mission is cancelled
+    pub previous_action_items: SelectableList<ActionItem>,
+    pub previous_context_items: SelectableList<(String, String)>,
+    // restored when cancelled, a new mission fails it once it is executed
+    pub previous_execution_id: Option<String>,
+    // set when the task refines the current actions
+    pub feedback: Option<String>,
+}
+
+impl MissionTask {
+    pub fn spawn(
+        backend: Arc<dyn MissionBackend>,
+        sender: UnboundedSender<AppEvent>,
+        request: MissionRequest,
+        previous_action_items: SelectableList<ActionItem>,
+        previous_context_items: SelectableList<(String, String)>,
+        previous_execution_id: Option<String>,
+    ) -> Self {
+        let id = NEXT_MISSION_TASK_ID.fetch_add(1, Ordering::Relaxed);
+        let feedback = request
+            .refinement
+            .as_ref()
+            .map(|refinement| refinement.feedback.clone());
+        let handle = spawn_mission(backend, sender, id, request);
+        Self {
+            id,
+            phase: String::from("Preparing execution..."),
+            phase_started_at: Instant::now(),
+            abort_handle: Arc::new(handle.abort_handle()),
+            previous_action_items,
+            previous_context_items,
+            previous_execution_id,
+            feedback,
+        }
+    }
+
+    pub fn abort(&self) {
+        self.abort_handle.abort();
+    }
+
+    pub fn set_phase(&mut self, phase: String) {
+        self.phase = phase;
+        self.phase_started_at = Instant::now();
+    }
+}
+
+pub struct MissionRequest {
+    pub mission_data: MissionData,
+    // when None, the context files are generated by the api
+    pub context_files: Option<Vec<String>>,
+    pub refinement: Option<Refinement>,
+    // sent with their content: the output of command items and the files reduced to fit
+    // the token budget
+    pub inline_context_files: Vec<MissionExecutionContextFile>,
+}
+
+pub fn build_mission_data(project_dir: &str, message: &str) -> MissionData {
+    let pathinfo = code_analyst::get_path_info(project_dir).unwrap_or_default();
+    MissionData {
+        project_dir: project_dir.to_string(),
+        message: message.to_string(),
+        project_files: code_analyst::project_files::get_project_files(
+            LocalPath(project_dir.to_string()),
+            &pathinfo.code_language,
+            &pathinfo.framework,
+        ),
+        code_language: pathinfo.code_language,
+        framework: pathinfo.framework,
+    }
+}
+
+fn spawn_mission(
+    backend: Arc<dyn MissionBackend>,
+    sender: UnboundedSender<AppEvent>,
+    task_id: usize,
+    request: MissionRequest,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let event = match run_mission(backend.as_ref(), &sender, task_id, request).await {
+            Ok((execution, snapshot)) => MissionEvent::Executed(execution, snapshot),
+            Err(err) => MissionEvent::Failed(err.message),
+        };
+        sender.send(AppEvent::Mission(task_id, event)).ok();
+    })
+}
+
+async fn run_mission(
+    backend: &dyn MissionBackend,
+    sender: &UnboundedSender<AppEvent>,
+    task_id: usize,
+    request: MissionRequest,
+) -> Result<(Execution, ProjectSnapshot), ApiError> {
+    let send_phase = |phase: &str| {
+        sender
+            .send(AppEvent::Mission(
+                task_id,
+                MissionEvent::Phase(phase.to_string()),
+            ))
+            .ok();
+    };
+    let res_ctx = match request.context_files {
+        Some(context_files) => CreateMissionResponse {
+            mission_id: String::from(""), // todo: does this throws an error? should be optional?
+            context_files,
+            mission_status: CodeMissionStatus::Created,
+        },
+        None => {
+            send_phase("Generating context files...");
+            let res_ctx = generate_context_files(backend, &request.mission_data).await?;
+            sender
+                .send(AppEvent::Mission(
+                    task_id,
+                    MissionEvent::ContextGenerated(res_ctx.clone()),
+                ))
+                .ok();
+            res_ctx
+        }
+    };
+    let mut mission_data = request.mission_data;
+    let mut extra_context_files = match &request.refinement {
+        Some(refinement) => {
+            send_phase("Refining actions...");
+            mission_data.message = refinement.message(&mission_data.message);
+            refinement.context_files()
+        }
+        None => {
+            send_phase("Executing mission...");
+            vec![]
+        }
+    };
+    let inline_items = request
+        .inline_context_files
+        .iter()
+        .map(|file| &file.path)
+        .filter(|path| item_command(path).is_none());
+    let paths: Vec<String> = res_ctx
+        .context_files
+        .iter()
+        .chain(inline_items)
+        .map(|item| parse_context_item(item).0.to_string())
+        .collect();
+    extra_context_files.extend(request.inline_context_files);
+    let project_dir = mission_data.project_dir.clone();
+    // taken as the files are read for the mission, so files changed while it runs are conflicts
+    let mut snapshot = take_snapshot(&project_dir, &paths);
+    let execution = execute_mission(backend, mission_data, res_ctx, extra_context_files).await?;
+    let targets: Vec<String> = execution
+        .actions
+        .iter()
+        .flat_map(|action| action.paths())
+        .filter(|path| !snapshot.contains_key(path))
+        .collect();
+    snapshot.extend(take_snapshot(&project_dir, &targets));
+    Ok((execution, snapshot))
+}
+
+pub async fn generate_context_files(
+    backend: &dyn MissionBackend,
+    mission_data: &MissionData,
+) -> Result<CreateMissionResponse, ApiError> {
+    let req_ctx = CreateMissionRequest {
+        mission_data: mission_data.clone(),
+    };
+    backend.create_mission(req_ctx).await
+}
+
+pub async fn execute_mission(
+    backend: &dyn MissionBackend,
+    mission_data: MissionData,
+    res_ctx: CreateMissionResponse,
+    extra_context_files: Vec<MissionExecutionContextFile>,
+) -> Result<Execution, ApiError> {
+    let mut context_files = res_ctx
+        .context_files
+        .iter()
+        .map(|file_path| MissionExecutionContextFile {
+            path: file_path.clone(),
+            content: get_context_file_content(&mission_data.project_dir, file_path),
+        })
+        .collect::<Vec<MissionExecutionContextFile>>();
+    context_files.extend(extra_context_files);
+    let req_exec = ExecuteMissionRequest {
+        mission_id: res_ctx.mission_id.clone(),
+        mission_data,
+        context_files,
+    };
+    backend.execute_mission(req_exec).await
+}
+
+/// Reads a context item, only the annotated lines of its ranges when it has any.
+pub fn get_context_file_content(project_dir: &str, file_path: &str) -> String {
+    read_context_item(project_dir, file_path).unwrap_or_default()
+}
+
+// returns the path in the project, refusing paths that would change files outside of it
+fn project_path(project_dir: &Path, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    let stays_inside = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !stays_inside {
+        anyhow::bail!("{} is outside of the project", path);
+    }
+    let full_path = project_dir.join(relative);
+    // symlinks can still lead outside, so the closest existing path is resolved
+    let existing_path = full_path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(project_dir);
+    if !existing_path.canonicalize()?.starts_with(project_dir) {
+        anyhow::bail!("{} is outside of the project", path);
+    }
+    Ok(full_path)
+}
+
+/// Writes the actions to the project, stopping at the first one that fails.
+///
+/// Nothing is written when an action is incomplete or has a path outside of the project.
+pub fn apply_actions(project_dir: &str, actions: &[ProposedAction]) -> Result<()> {
+    let canonical_project_dir = Path::new(project_dir).canonicalize()?;
+    for action in actions {
+        match action.action_type {
+            ActionType::CreateFile | ActionType::UpdateFile if action.content.is_none() => {
+                anyhow::bail!("Missing content to write {}", action.path)
+            }
+            ActionType::RenameFile if action.new_path.is_none() => {
+                anyhow::bail!("Missing new path to rename {}", action.path)
+            }
+            _ => {}
+        }
+        for path in action.paths() {
+            project_path(&canonical_project_dir, &path)?;
+        }
+    }
+    for action in actions {
+        let path = project_path(&canonical_project_dir, &action.path)?;
+        match action.action_type {
+            ActionType::CreateFile | ActionType::UpdateFile => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let content = action.content.as_deref().unwrap_or_default();
+                if let Err(err) = std::fs::write(&path, content) {
+                    anyhow::bail!("Failed to write {}: {}", action.path, err);
+                }
+            }
+            ActionType::DeleteFile => {
+                if let Err(err) = std::fs::remove_file(&path) {
+                    anyhow::bail!("Failed to delete {}: {}", action.path, err);
+                }
+            }
+            ActionType::RenameFile => {
+                let new_path = project_path(
+                    &canonical_project_dir,
+                    action.new_path.as_deref().unwrap_or_default(),
+                )?;
+                if let Some(parent) = new_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                if let Err(err) = std::fs::rename(&path, &new_path) {
+                    anyhow::bail!("Failed to rename {}: {}", action.path, err);
+                }
+                if let Some(content) = &action.content {
+                    std::fs::write(&new_path, content)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use crate::config;
+
+/// Where a preset is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Display)]
+pub enum PresetScope {
+    // in the config dir, only for this user
+    #[default]
+    #[strum(serialize = "personal")]
+    Personal,
+    // in `<project>/.lili/presets.json`, committed to share it with the team
+    #[strum(serialize = "shared")]
+    Shared,
+}
+
+/// A named set of context items, saved to rebuild the same context later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextPreset {
+    pub name: String,
+    pub items: Vec<String>,
+    #[serde(skip)]
+    pub scope: PresetScope,
+}
+
+impl ContextPreset {
+    pub fn new(name: &str, items: Vec<String>, scope: PresetScope) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            items,
+            scope,
+        }
+    }
+}
+
+fn presets_path(project_dir: &str, scope: PresetScope) -> PathBuf {
+    match scope {
+        PresetScope::Personal => config::config_dir()
+            .join("presets")
+            .join(format!("{}.json", config::project_file_name(project_dir))),
+        PresetScope::Shared => PathBuf::from(project_dir)
+            .join(".lili")
+            .join("presets.json"),
+    }
+}
+
+fn read_presets(project_dir: &str, scope: PresetScope) -> Result<Vec<ContextPreset>> {
+    let path = presets_path(project_dir, scope);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return Ok(vec![]),
+    };
+    let mut presets: Vec<ContextPreset> = serde_json::from_str(&content)
+        .map_err(|err| anyhow::anyhow!("Invalid {}: {}", path.display(), err))?;
+    for preset in presets.iter_mut() {
+        preset.scope = scope;
+    }
+    Ok(presets)
+}
+
+fn write_presets(project_dir: &str, scope: PresetScope, presets: &[ContextPreset]) -> Result<()> {
+    let path = presets_path(project_dir, scope);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // pretty, the shared file is reviewed in diffs
+    std::fs::write(path, serde_json::to_string_pretty(presets)? + "\n")?;
+    Ok(())
+}
+
+/// Returns the shared presets of the project, then the personal ones, each sorted by name.
+pub fn load_presets(project_dir: &str) -> Result<Vec<ContextPreset>> {
+    let mut presets = vec![];
+    for scope in [PresetScope::Shared, PresetScope::Personal] {
+        let mut scoped = read_presets(project_dir, scope)?;
+        scoped.sort_by_key(|preset| preset.name.to_lowercase());
+        presets.extend(scoped);
+    }
+    Ok(presets)
+}
+
+/// Saves the preset, replacing the one with the same name in its scope.
+pub fn save_preset(project_dir: &str, preset: ContextPreset) -> Result<()> {
+    let mut presets = read_presets(project_dir, preset.scope)?;
+    presets.retain(|saved| saved.name != preset.name);
+    let scope = preset.scope;
+    presets.push(preset);
+    write_presets(project_dir, scope, &presets)
+}
+
+pub fn delete_preset(project_dir: &str, preset: &ContextPreset) -> Result<()> {
+    let mut presets = read_presets(project_dir, preset.scope)?;
+    presets.retain(|saved| saved.name != preset.name);
+    write_presets(project_dir, preset.scope, &presets)
+}
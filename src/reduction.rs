@@ -0,0 +1,343 @@
+use std::path::Path;
+
+use lilicore::code_missions_api::{MissionData, MissionExecutionContextFile};
+use strum::Display;
+
+use crate::{
+    context_commands::item_command,
+    missions::Refinement,
+    utils::{line_ranges::parse_context_item, list::SelectableList, llm_tokens::TokenCounter},
+};
+
+// lines kept above and below every line mentioning an identifier of the message
+const FOCUS_LINES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum Reduction {
+    #[strum(serialize = "full")]
+    Full,
+    // without comment and blank lines
+    #[strum(serialize = "no comments")]
+    StripComments,
+    // only the lines declaring something
+    #[strum(serialize = "outline")]
+    Outline,
+    // only the regions around the identifiers mentioned in the message
+    #[strum(serialize = "focused")]
+    Focus,
+}
+
+/// A context file and the way it is cut down to fit the token budget.
+#[derive(Debug, Clone)]
+pub struct ReducedFile {
+    pub path: String,
+    pub reduction: Reduction,
+    pub content: String,
+    pub tokens: usize,
+    original: String,
+}
+
+impl ReducedFile {
+    pub fn new(path: &str, original: String, counter: &TokenCounter) -> Self {
+        Self {
+            path: path.to_string(),
+            reduction: Reduction::Full,
+            tokens: counter.count(&original),
+            content: original.clone(),
+            original,
+        }
+    }
+
+    /// Returns the content reduced the given way, None when the reduction does not apply.
+    fn reduced_content(&self, reduction: Reduction, identifiers: &[String]) -> Option<String> {
+        let content = match reduction {
+            Reduction::Full => return Some(self.original.clone()),
+            Reduction::StripComments => strip_comments(&self.path, &self.original),
+            Reduction::Outline => outline(&self.original),
+            Reduction::Focus => focus(&self.original, identifiers)?,
+        };
+        match content.trim().is_empty() {
+            true => None,
+            false => Some(content),
+        }
+    }
+
+    /// Reduces the file the given way, returns false when the reduction does not apply.
+    pub fn apply(
+        &mut self,
+        reduction: Reduction,
+        identifiers: &[String],
+        counter: &TokenCounter,
+    ) -> bool {
+        let content = match self.reduced_content(reduction, identifiers) {
+            Some(content) => content,
+            None => return false,
+        };
+        self.tokens = counter.count(&content);
+        self.content = content;
+        self.reduction = reduction;
+        true
+    }
+
+    /// The file as sent to the mission, the path tells the model it is not the whole file.
+    pub fn to_context_file(&self) -> MissionExecutionContextFile {
+        let path = match self.reduction {
+            Reduction::Full => self.path.clone(),
+            reduction => format!("{} ({})", self.path, reduction),
+        };
+        MissionExecutionContextFile {
+            path,
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// A mission waiting for the user to confirm how its context files are reduced.
+#[derive(Debug, Clone)]
+pub struct ContextReduction {
+    pub files: SelectableList<ReducedFile>,
+    // tokens left for the context files by the message, the project file list and the
+    // feedback of a refinement
+    pub budget: usize,
+    pub identifiers: Vec<String>,
+    pub mission_data: MissionData,
+    pub refinement: Option<Refinement>,
+}
+
+impl ContextReduction {
+    /// Reduces the context files until they fit the budget, the last ones first.
+    ///
+    /// Comments are stripped before anything else, only then are files focused on the
+    /// identifiers of the message, or outlined when they mention none.
+    pub fn plan(
+        files: Vec<ReducedFile>,
+        budget: usize,
+        counter: &TokenCounter,
+        mission_data: MissionData,
+        refinement: Option<Refinement>,
+    ) -> Self {
+        let mut reduction = Self {
+            files: SelectableList::new(Some(0), files),
+            budget,
+            identifiers: mentioned_identifiers(&mission_data.message),
+            mission_data,
+            refinement,
+        };
+        let file_count = reduction.files.len();
+        for reductions in [
+            &[Reduction::StripComments][..],
+            &[Reduction::Focus, Reduction::Outline][..],
+        ] {
+            for index in (0..file_count).rev() {
+                if reduction.fits() {
+                    return reduction;
+                }
+                let identifiers = reduction.identifiers.clone();
+                let file = &mut reduction.files.items[index];
+                for candidate in reductions {
+                    let tokens = file.tokens;
+                    let mut reduced = file.clone();
+                    // a reduction that saves nothing only loses content
+                    if reduced.apply(*candidate, &identifiers, counter) && reduced.tokens < tokens {
+                        *file = reduced;
+                        break;
+                    }
+                }
+            }
+        }
+        reduction
+    }
+
+    pub fn tokens(&self) -> usize {
+        self.files.items.iter().map(|file| file.tokens).sum()
+    }
+
+    pub fn fits(&self) -> bool {
+        self.tokens() <= self.budget
+    }
+
+    /// Changes the reduction of the selected file, returns false when it does not apply.
+    pub fn set_selected_reduction(&mut self, reduction: Reduction, counter: &TokenCounter) -> bool {
+        let identifiers = self.identifiers.clone();
+        match self.files.get_selected_item_mut() {
+            Some(file) => file.apply(reduction, &identifiers, counter),
+            None => false,
+        }
+    }
+
+    /// Returns the paths of the whole files, and the reduced files and command outputs sent
+    /// with their content.
+    pub fn context_files(&self) -> (Vec<String>, Vec<MissionExecutionContextFile>) {
+        let (full_files, reduced_files): (Vec<&ReducedFile>, Vec<&ReducedFile>) =
+            self.files.items.iter().partition(|file| {
+                file.reduction == Reduction::Full && item_command(&file.path).is_none()
+            });
+        (
+            full_files.iter().map(|file| file.path.clone()).collect(),
+            reduced_files
+                .iter()
+                .map(|file| file.to_context_file())
+                .collect(),
+        )
+    }
+}
+
+// block comments are only known for the languages using `//`
+fn line_comment_prefix(item: &str) -> Option<&'static str> {
+    let (path, _) = parse_context_item(item);
+    let extension = Path::new(path).extension()?.to_str()?;
+    match extension {
+        "rs" | "js" | "jsx" | "ts" | "tsx" | "go" | "java" | "kt" | "swift" | "c" | "h" | "cpp"
+        | "hpp" | "cs" | "scala" | "dart" | "php" => Some("//"),
+        "py" | "rb" | "sh" | "yml" | "yaml" | "toml" | "pl" | "r" | "ex" | "exs" => Some("#"),
+        "sql" | "lua" | "hs" => Some("--"),
+        _ => None,
+    }
+}
+
+// the lines of line ranges start with their number, like `12| `
+fn strip_line_number(line: &str) -> &str {
+    match line.split_once("| ") {
+        Some((number, code)) if number.trim().parse::<usize>().is_ok() => code,
+        _ => line,
+    }
+}
+
+fn strip_comments(path: &str, content: &str) -> String {
+    let prefix = line_comment_prefix(path);
+    let mut in_block_comment = false;
+    let mut lines = vec![];
+    for line in content.lines() {
+        let trimmed = strip_line_number(line).trim();
+        if in_block_comment {
+            in_block_comment = !trimmed.contains("*/");
+            continue;
+        }
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(prefix) = prefix {
+            if trimmed.starts_with(prefix) {
+                continue;
+            }
+            if prefix == "//" && trimmed.starts_with("/*") {
+                in_block_comment = !trimmed.contains("*/");
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+fn outline(content: &str) -> String {
+    const MODIFIERS: [&str; 10] = [
+        "pub(crate) ",
+        "pub ",
+        "export ",
+        "default ",
+        "async ",
+        "static ",
+        "public ",
+        "private ",
+        "protected ",
+        "unsafe ",
+    ];
+    const DECLARATIONS: [&str; 17] = [
+        "fn ",
+        "struct ",
+        "enum ",
+        "trait ",
+        "impl ",
+        "impl<",
+        "mod ",
+        "type ",
+        "const ",
+        "class ",
+        "interface ",
+        "def ",
+        "function ",
+        "func ",
+        "use ",
+        "import ",
+        "from ",
+    ];
+    content
+        .lines()
+        .filter(|line| {
+            let mut declaration = strip_line_number(line).trim_start();
+            while let Some(modifier) = MODIFIERS
+                .iter()
+                .find(|modifier| declaration.starts_with(*modifier))
+            {
+                declaration = &declaration[modifier.len()..];
+            }
+            DECLARATIONS
+                .iter()
+                .any(|keyword| declaration.starts_with(keyword))
+        })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+fn focus(content: &str, identifiers: &[String]) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for (index, line) in lines.iter().enumerate() {
+        if !identifiers
+            .iter()
+            .any(|identifier| line.contains(identifier.as_str()))
+        {
+            continue;
+        }
+        let start = index.saturating_sub(FOCUS_LINES);
+        let end = (index + FOCUS_LINES).min(lines.len() - 1);
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    if ranges.is_empty() {
+        return None;
+    }
+    let mut focused = vec![];
+    let mut next_line = 0;
+    for (start, end) in ranges {
+        if start > next_line {
+            focused.push(format!("... (lines {}-{} left out)", next_line + 1, start));
+        }
+        focused.extend(lines[start..=end].iter().map(|line| line.to_string()));
+        next_line = end + 1;
+    }
+    if next_line < lines.len() {
+        focused.push(format!(
+            "... (lines {}-{} left out)",
+            next_line + 1,
+            lines.len()
+        ));
+    }
+    Some(focused.join("\n"))
+}
+
+/// Returns the words of the message that look like code: quoted in backticks,
+/// or containing an underscore or an inner capital letter.
+pub fn mentioned_identifiers(message: &str) -> Vec<String> {
+    let is_separator = |c: char| !(c.is_alphanumeric() || c == '_');
+    let quoted = message
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .flat_map(|quote| quote.split(is_separator))
+        .filter(|word| word.len() >= 2);
+    let code_like = message.split(is_separator).filter(|word| {
+        word.len() >= 3
+            && (word.contains('_') || word.chars().skip(1).any(|c| c.is_ascii_uppercase()))
+    });
+    let mut identifiers: Vec<String> = vec![];
+    for word in quoted.chain(code_like) {
+        if !identifiers.iter().any(|identifier| identifier == word) {
+            identifiers.push(word.to_string());
+        }
+    }
+    identifiers
+}
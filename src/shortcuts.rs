@@ -1,10 +1,11 @@
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use lilicore::{git_repo::get_current_branch_name, shell::run_shell_command};
 
 use crate::{
     app::{AppScreen, AppState, FocusedBlock},
     components::header::HeaderStatus,
+    views::cancel_mission,
 };
 
 #[derive(Debug, PartialEq, Clone)]
@@ -98,6 +99,24 @@ pub fn handle_global_shortcuts(
     Ok(ShortcutHandlerResponse::Continue)
 }
 
+// handled before any view, so they also work while typing in a text input
+pub fn handle_mission_shortcuts(
+    state: &mut AppState,
+    key: &KeyEvent,
+) -> Result<ShortcutHandlerResponse> {
+    let mission_started = state.mission_task.is_some() || state.deferred_mission.is_some();
+    if key.kind == KeyEventKind::Release || !mission_started {
+        return Ok(ShortcutHandlerResponse::Continue);
+    }
+
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        cancel_mission(state);
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+
+    Ok(ShortcutHandlerResponse::Continue)
+}
+
 pub fn handle_text_input_event(
     state: &mut AppState,
     key: &KeyEvent,
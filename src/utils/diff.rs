@@ -0,0 +1,441 @@
+use std::{collections::HashSet, ops::Range};
+
+use similar::{ChangeTag, DiffOp, DiffTag, TextDiff};
+
+use crate::missions::{ActionType, ProposedAction};
+
+// lines of unchanged content shown around each change
+const CONTEXT_RADIUS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    pub tag: ChangeTag,
+    pub content: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffHunk {
+    // e.g. "@@ -1,3 +1,4 @@"
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+    pub change: HunkChange,
+}
+
+/// The lines a hunk removes and adds, without the unchanged lines around them.
+///
+/// Rejected hunks are kept by their change, so they still match the same hunk when the
+/// diff is computed again from a file that changed on disk, and match nothing once it is gone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HunkChange {
+    pub old_lines: String,
+    pub new_lines: String,
+}
+
+// the bool is true for the parts that changed inside the line
+pub type DiffSegments = Vec<(bool, String)>;
+
+/// One side of a row of the side-by-side diff.
+#[derive(Debug, Clone)]
+pub struct SplitDiffCell {
+    pub line_number: usize,
+    pub tag: ChangeTag,
+    pub segments: DiffSegments,
+}
+
+/// A row of the side-by-side diff, a missing side is rendered as an empty line.
+#[derive(Debug, Clone)]
+pub struct SplitDiffRow {
+    pub old: Option<SplitDiffCell>,
+    pub new: Option<SplitDiffCell>,
+}
+
+/// Returns the content of the file the action changes, empty for new files.
+pub fn action_original_content(project_dir: &str, action: &ProposedAction) -> String {
+    match action.action_type {
+        ActionType::CreateFile => String::new(),
+        _ => {
+            std::fs::read_to_string(format!("{}/{}", project_dir, action.path)).unwrap_or_default()
+        }
+    }
+}
+
+/// Returns the content of the file after the action, empty for deleted files.
+pub fn action_proposed_content(project_dir: &str, action: &ProposedAction) -> String {
+    match action.action_type {
+        ActionType::DeleteFile => String::new(),
+        ActionType::RenameFile if action.content.is_none() => {
+            action_original_content(project_dir, action)
+        }
+        _ => action.content.clone().unwrap_or_default(),
+    }
+}
+
+pub fn diff_hunks(original: &str, proposed: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(original, proposed);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let mut unified_diff = diff.unified_diff();
+    unified_diff.context_radius(CONTEXT_RADIUS);
+    unified_diff
+        .iter_hunks()
+        .map(|hunk| DiffHunk {
+            header: hunk.header().to_string(),
+            lines: hunk
+                .iter_changes()
+                .map(|change| DiffLine {
+                    tag: change.tag(),
+                    content: trim_line_ending(change.value()),
+                })
+                .collect(),
+            change: hunk_change(hunk.ops(), old_lines, new_lines),
+        })
+        .collect()
+}
+
+fn hunk_change(ops: &[DiffOp], old_lines: &[&str], new_lines: &[&str]) -> HunkChange {
+    let mut change = HunkChange::default();
+    for op in ops {
+        let (diff_tag, old_range, new_range) = op.as_tag_tuple();
+        if diff_tag != DiffTag::Equal {
+            change.old_lines.push_str(&old_lines[old_range].concat());
+            change.new_lines.push_str(&new_lines[new_range].concat());
+        }
+    }
+    change
+}
+
+/// Applies the proposed content except for the rejected hunks, which keep the original lines.
+///
+/// Returns None when a rejected hunk is not part of the diff between the two contents.
+pub fn merge_hunks(
+    original: &str,
+    proposed: &str,
+    rejected_hunks: &HashSet<HunkChange>,
+) -> Option<String> {
+    if rejected_hunks.is_empty() {
+        return Some(proposed.to_string());
+    }
+    let diff = TextDiff::from_lines(original, proposed);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let groups = diff.grouped_ops(CONTEXT_RADIUS);
+    let changes: Vec<HunkChange> = groups
+        .iter()
+        .map(|group| hunk_change(group, old_lines, new_lines))
+        .collect();
+    if !rejected_hunks.iter().all(|change| changes.contains(change)) {
+        return None;
+    }
+    let mut merged = String::new();
+    let mut old_position = 0;
+    for (group, change) in groups.iter().zip(changes.iter()) {
+        let rejected = rejected_hunks.contains(change);
+        for op in group {
+            let (diff_tag, old_range, new_range) = op.as_tag_tuple();
+            // lines between two hunks are unchanged
+            merged.push_str(&old_lines[old_position..old_range.start].concat());
+            match (diff_tag, rejected) {
+                (DiffTag::Equal, _) | (_, true) => {
+                    merged.push_str(&old_lines[old_range.clone()].concat())
+                }
+                (_, false) => merged.push_str(&new_lines[new_range].concat()),
+            }
+            old_position = old_range.end;
+        }
+    }
+    merged.push_str(&old_lines[old_position..].concat());
+    Some(merged)
+}
+
+/// Applies the changes made from `base` to `proposed` on top of `current`.
+///
+/// Returns None when both sides changed the same (or adjacent) lines differently.
+pub fn three_way_merge(base: &str, current: &str, proposed: &str) -> Option<String> {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let current_changes = line_changes(base, current);
+    let proposed_changes = line_changes(base, proposed);
+    let mut changes = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < current_changes.len() || j < proposed_changes.len() {
+        match (current_changes.get(i), proposed_changes.get(j)) {
+            (Some(a), Some(b)) if a.0.start <= b.0.end && b.0.start <= a.0.end => {
+                if a != b {
+                    return None;
+                }
+                changes.push(a.clone());
+                i += 1;
+                j += 1;
+            }
+            (Some(a), Some(b)) if a.0.start < b.0.start => {
+                changes.push(a.clone());
+                i += 1;
+            }
+            (Some(a), None) => {
+                changes.push(a.clone());
+                i += 1;
+            }
+            (_, Some(b)) => {
+                changes.push(b.clone());
+                j += 1;
+            }
+            (None, None) => break,
+        }
+    }
+    let mut merged = String::new();
+    let mut position = 0;
+    for (range, lines) in changes {
+        merged.push_str(&base_lines[position..range.start].concat());
+        merged.push_str(&lines);
+        position = range.end;
+    }
+    merged.push_str(&base_lines[position..].concat());
+    Some(merged)
+}
+
+// the ranges of base lines that changed, with the lines replacing them
+fn line_changes(base: &str, changed: &str) -> Vec<(Range<usize>, String)> {
+    let diff = TextDiff::from_lines(base, changed);
+    let new_lines = diff.new_slices();
+    let mut changes: Vec<(Range<usize>, String)> = vec![];
+    for op in diff.ops() {
+        let (diff_tag, old_range, new_range) = op.as_tag_tuple();
+        if diff_tag == DiffTag::Equal {
+            continue;
+        }
+        let lines = new_lines[new_range].concat();
+        // a delete followed by an insert is a single replacement
+        match changes.last_mut() {
+            Some(last) if last.0.end == old_range.start => {
+                last.0.end = old_range.end;
+                last.1.push_str(&lines);
+            }
+            _ => changes.push((old_range, lines)),
+        }
+    }
+    changes
+}
+
+/// Aligns the original and proposed lines so replaced lines end up on the same row.
+pub fn split_diff_rows(original: &str, proposed: &str) -> Vec<SplitDiffRow> {
+    let diff = TextDiff::from_lines(original, proposed);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let cell = |lines: &[&str], index: usize, tag: ChangeTag| SplitDiffCell {
+        line_number: index + 1,
+        tag,
+        segments: vec![(false, trim_line_ending(lines[index]))],
+    };
+    let mut rows = vec![];
+    for op in diff.ops() {
+        let (diff_tag, old_range, new_range) = op.as_tag_tuple();
+        match diff_tag {
+            DiffTag::Equal => {
+                for (old_index, new_index) in old_range.zip(new_range) {
+                    rows.push(SplitDiffRow {
+                        old: Some(cell(old_lines, old_index, ChangeTag::Equal)),
+                        new: Some(cell(new_lines, new_index, ChangeTag::Equal)),
+                    });
+                }
+            }
+            DiffTag::Delete => {
+                for old_index in old_range {
+                    rows.push(SplitDiffRow {
+                        old: Some(cell(old_lines, old_index, ChangeTag::Delete)),
+                        new: None,
+                    });
+                }
+            }
+            DiffTag::Insert => {
+                for new_index in new_range {
+                    rows.push(SplitDiffRow {
+                        old: None,
+                        new: Some(cell(new_lines, new_index, ChangeTag::Insert)),
+                    });
+                }
+            }
+            DiffTag::Replace => {
+                let len = old_range.len().max(new_range.len());
+                for offset in 0..len {
+                    let old_index = old_range.clone().nth(offset);
+                    let new_index = new_range.clone().nth(offset);
+                    let mut old = old_index.map(|index| cell(old_lines, index, ChangeTag::Delete));
+                    let mut new = new_index.map(|index| cell(new_lines, index, ChangeTag::Insert));
+                    if let (Some(old), Some(new)) = (&mut old, &mut new) {
+                        let (old_segments, new_segments) =
+                            inline_segments(&old.segments[0].1, &new.segments[0].1);
+                        old.segments = old_segments;
+                        new.segments = new_segments;
+                    }
+                    rows.push(SplitDiffRow { old, new });
+                }
+            }
+        }
+    }
+    rows
+}
+
+// splits a pair of replaced lines into the parts they share and the parts that changed
+fn inline_segments(old: &str, new: &str) -> (DiffSegments, DiffSegments) {
+    let diff = TextDiff::from_chars(old, new);
+    let mut old_segments: DiffSegments = vec![];
+    let mut new_segments: DiffSegments = vec![];
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+        match change.tag() {
+            ChangeTag::Equal => {
+                push_segment(&mut old_segments, false, value);
+                push_segment(&mut new_segments, false, value);
+            }
+            ChangeTag::Delete => push_segment(&mut old_segments, true, value),
+            ChangeTag::Insert => push_segment(&mut new_segments, true, value),
+        }
+    }
+    (old_segments, new_segments)
+}
+
+fn push_segment(segments: &mut DiffSegments, changed: bool, value: &str) {
+    match segments.last_mut() {
+        Some(last) if last.0 == changed => last.1.push_str(value),
+        _ => segments.push((changed, value.to_string())),
+    }
+}
+
+fn trim_line_ending(line: &str) -> String {
+    line.trim_end_matches(['\r', '\n']).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // twenty numbered lines, changes on line 2 and line 18 end up in two hunks
+    fn lines(changes: &[(usize, &str)]) -> String {
+        (1..=20)
+            .map(
+                |number| match changes.iter().find(|(line, _)| *line == number) {
+                    Some((_, content)) => format!("{}\n", content),
+                    None => format!("line {}\n", number),
+                },
+            )
+            .collect()
+    }
+
+    fn changes(original: &str, proposed: &str) -> Vec<HunkChange> {
+        diff_hunks(original, proposed)
+            .into_iter()
+            .map(|hunk| hunk.change)
+            .collect()
+    }
+
+    #[test]
+    fn merges_only_the_accepted_hunks() {
+        let original = lines(&[]);
+        let proposed = lines(&[(2, "two"), (18, "eighteen")]);
+        let hunks = changes(&original, &proposed);
+        assert_eq!(hunks.len(), 2);
+
+        let rejected = HashSet::from([hunks[1].clone()]);
+        assert_eq!(
+            merge_hunks(&original, &proposed, &rejected),
+            Some(lines(&[(2, "two")]))
+        );
+        assert_eq!(
+            merge_hunks(&original, &proposed, &HashSet::new()),
+            Some(proposed.clone())
+        );
+    }
+
+    #[test]
+    fn keeps_the_original_when_every_hunk_is_rejected() {
+        let original = lines(&[]);
+        let proposed = lines(&[(2, "two"), (18, "eighteen")]);
+        let rejected = changes(&original, &proposed).into_iter().collect();
+        assert_eq!(merge_hunks(&original, &proposed, &rejected), Some(original));
+    }
+
+    #[test]
+    fn matches_rejected_hunks_after_the_file_changed() {
+        let original = lines(&[]);
+        let proposed = lines(&[(2, "two"), (18, "eighteen")]);
+        let rejected = HashSet::from([changes(&original, &proposed)[0].clone()]);
+
+        // lines added on top shift the hunk, it is still the same change
+        let shifted = format!("header\n{}", original);
+        let proposed_on_shifted = format!("header\n{}", proposed);
+        assert_eq!(
+            merge_hunks(&shifted, &proposed_on_shifted, &rejected),
+            Some(format!("header\n{}", lines(&[(18, "eighteen")])))
+        );
+
+        // the rejected change is not in the diff anymore, nothing is guessed
+        let rewritten = lines(&[(2, "two")]);
+        assert_eq!(merge_hunks(&rewritten, &proposed, &rejected), None);
+    }
+
+    #[test]
+    fn merges_changes_on_different_lines() {
+        let base = lines(&[]);
+        let current = lines(&[(1, "one")]);
+        let proposed = lines(&[(18, "eighteen")]);
+        assert_eq!(
+            three_way_merge(&base, &current, &proposed),
+            Some(lines(&[(1, "one"), (18, "eighteen")]))
+        );
+    }
+
+    #[test]
+    fn refuses_to_merge_overlapping_changes() {
+        let base = lines(&[]);
+        assert_eq!(
+            three_way_merge(&base, &lines(&[(5, "five")]), &lines(&[(5, "FIVE")])),
+            None
+        );
+        // adjacent lines are too close to tell which side is right
+        assert_eq!(
+            three_way_merge(&base, &lines(&[(5, "five")]), &lines(&[(6, "six")])),
+            None
+        );
+        // the same change on both sides is kept once
+        assert_eq!(
+            three_way_merge(&base, &lines(&[(5, "five")]), &lines(&[(5, "five")])),
+            Some(lines(&[(5, "five")]))
+        );
+    }
+
+    #[test]
+    fn aligns_replaced_lines_on_the_same_row() {
+        let rows = split_diff_rows("a\nold line\nb\n", "a\nnew line\nadded\nb\n");
+        let sides: Vec<(Option<usize>, Option<usize>)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.old.as_ref().map(|cell| cell.line_number),
+                    row.new.as_ref().map(|cell| cell.line_number),
+                )
+            })
+            .collect();
+        assert_eq!(
+            sides,
+            vec![
+                (Some(1), Some(1)),
+                (Some(2), Some(2)),
+                (None, Some(3)),
+                (Some(3), Some(4)),
+            ]
+        );
+
+        let old = rows[1].old.as_ref().unwrap();
+        let new = rows[1].new.as_ref().unwrap();
+        assert_eq!(old.tag, ChangeTag::Delete);
+        assert_eq!(new.tag, ChangeTag::Insert);
+        assert_eq!(
+            old.segments,
+            vec![(true, String::from("old")), (false, String::from(" line"))]
+        );
+        assert_eq!(
+            new.segments,
+            vec![(true, String::from("new")), (false, String::from(" line"))]
+        );
+        assert_eq!(rows[2].new.as_ref().unwrap().tag, ChangeTag::Insert);
+    }
+}
@@ -0,0 +1,61 @@
+use std::{io, path::Path, process::Command};
+
+use anyhow::Result;
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+/// Opens `content` in `$VISUAL` (or `$EDITOR`) and returns the edited buffer.
+///
+/// The terminal leaves raw mode and the alternate screen while the editor runs, the caller
+/// has to stop reading terminal events and redraw everything afterwards.
+pub fn edit_in_external_editor(content: &str, file_path: &str) -> Result<String> {
+    let editor = match std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")) {
+        Ok(editor) if !editor.trim().is_empty() => editor,
+        _ => anyhow::bail!("Set $VISUAL or $EDITOR to edit actions"),
+    };
+    // keep the file name so the editor picks the right syntax highlighting
+    let file_name = Path::new(file_path)
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .unwrap_or("action");
+    let temp_path = std::env::temp_dir().join(format!("lili-{}-{}", std::process::id(), file_name));
+    std::fs::write(&temp_path, content)?;
+
+    suspend_terminal()?;
+    // the editor may come with arguments, e.g. `code --wait`
+    let mut editor_args = editor.split_whitespace();
+    let status = Command::new(editor_args.next().unwrap_or_default())
+        .args(editor_args)
+        .arg(&temp_path)
+        .status();
+    resume_terminal()?;
+
+    let edited_content = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&temp_path),
+        Ok(status) => {
+            std::fs::remove_file(&temp_path).ok();
+            anyhow::bail!("{} exited with {}", editor, status);
+        }
+        Err(err) => {
+            std::fs::remove_file(&temp_path).ok();
+            anyhow::bail!("Failed to run {}: {}", editor, err);
+        }
+    };
+    std::fs::remove_file(&temp_path).ok();
+    Ok(edited_content?)
+}
+
+fn suspend_terminal() -> Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    Ok(())
+}
+
+fn resume_terminal() -> Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    Ok(())
+}
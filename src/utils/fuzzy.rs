@@ -0,0 +1,211 @@
+// scores of a matched character and the bonuses added to it
+const MATCH: i64 = 16;
+// right after the previous matched character
+const CONSECUTIVE: i64 = 12;
+// first character of the path or of a directory or file name
+const SEGMENT_START: i64 = 10;
+// after `_`, `-`, `.` or a space, or a camelCase hump
+const WORD_START: i64 = 6;
+// in the file name rather than in a directory
+const FILE_NAME: i64 = 4;
+// lost for every character skipped between two matched ones
+const GAP: i64 = 1;
+
+const NO_MATCH: i64 = i64::MIN / 2;
+
+/// How well a query matches a text, and the indexes of the matched characters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    // char indexes, sorted
+    pub indexes: Vec<usize>,
+}
+
+fn char_bonus(chars: &[char], index: usize, file_name_start: usize) -> i64 {
+    let previous = match index {
+        0 => None,
+        _ => Some(chars[index - 1]),
+    };
+    let mut bonus = match previous {
+        None | Some('/') => SEGMENT_START,
+        Some('_' | '-' | '.' | ' ') => WORD_START,
+        Some(previous) if previous.is_lowercase() && chars[index].is_uppercase() => WORD_START,
+        _ => 0,
+    };
+    if index >= file_name_start {
+        bonus += FILE_NAME;
+    }
+    bonus
+}
+
+// finds the best placement of the term in the text, the term being lowercase
+fn match_term(term: &[char], chars: &[char], lowercase: &[char]) -> Option<FuzzyMatch> {
+    let (m, n) = (term.len(), chars.len());
+    if m > n {
+        return None;
+    }
+    let file_name_start = chars
+        .iter()
+        .rposition(|c| *c == '/')
+        .map_or(0, |index| index + 1);
+    // scores[i][j] is the best score of term[..=i] with term[i] matched at j,
+    // previous[i][j] where term[i - 1] was matched then
+    let mut scores = vec![vec![NO_MATCH; n]; m];
+    let mut previous = vec![vec![0; n]; m];
+    for i in 0..m {
+        // the best score of term[i - 1] matched before j, minus the gap up to j
+        let mut best: Option<(i64, usize)> = None;
+        for j in 0..n {
+            if i > 0 && j > 0 {
+                best = best.map(|(score, index)| (score - GAP, index));
+                let candidate = scores[i - 1][j - 1];
+                let better = match best {
+                    Some((score, _)) => candidate >= score,
+                    None => true,
+                };
+                if candidate > NO_MATCH && better {
+                    best = Some((candidate, j - 1));
+                }
+            }
+            if lowercase[j] != term[i] {
+                continue;
+            }
+            let bonus = MATCH + char_bonus(chars, j, file_name_start);
+            if i == 0 {
+                scores[i][j] = bonus;
+                continue;
+            }
+            let consecutive = match j > 0 && scores[i - 1][j - 1] > NO_MATCH {
+                true => Some((scores[i - 1][j - 1] + CONSECUTIVE, j - 1)),
+                false => None,
+            };
+            let from = match (consecutive, best) {
+                (Some(consecutive), Some(best)) if best.0 > consecutive.0 => Some(best),
+                (Some(consecutive), _) => Some(consecutive),
+                (None, best) => best,
+            };
+            if let Some((score, index)) = from {
+                scores[i][j] = score + bonus;
+                previous[i][j] = index;
+            }
+        }
+    }
+    let (end, score) = scores[m - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, score)| **score > NO_MATCH)
+        .max_by_key(|(_, score)| **score)?;
+    let mut indexes = vec![end];
+    for i in (1..m).rev() {
+        indexes.push(previous[i][indexes[indexes.len() - 1]]);
+    }
+    indexes.reverse();
+    Some(FuzzyMatch {
+        score: *score,
+        indexes,
+    })
+}
+
+/// Matches every word of the query, in any order, against the text. The letters of a word
+/// must appear in order, but not next to each other: `msnview` matches `mission_view.rs`.
+///
+/// Ignores case, an empty query matches everything with a score of 0.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let chars: Vec<char> = text.chars().collect();
+    let lowercase: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let mut total = FuzzyMatch {
+        score: 0,
+        indexes: vec![],
+    };
+    for word in query.split_whitespace() {
+        let term: Vec<char> = word.to_lowercase().chars().collect();
+        let word_match = match_term(&term, &chars, &lowercase)?;
+        total.score += word_match.score;
+        total.indexes.extend(word_match.indexes);
+    }
+    total.indexes.sort();
+    total.indexes.dedup();
+    Some(total)
+}
+
+/// Returns the items matching the query with their matched characters, the best first.
+///
+/// Equal scores keep the shorter texts first, an empty query keeps the order of the items.
+pub fn fuzzy_filter<T: Clone>(
+    items: &[T],
+    query: &str,
+    text: impl Fn(&T) -> &str,
+) -> Vec<(T, Vec<usize>)> {
+    if query.trim().is_empty() {
+        return items.iter().map(|item| (item.clone(), vec![])).collect();
+    }
+    let mut matches: Vec<(i64, usize, &T, Vec<usize>)> = items
+        .iter()
+        .filter_map(|item| {
+            let text = text(item);
+            let fuzzy_match = fuzzy_match(query, text)?;
+            Some((
+                fuzzy_match.score,
+                text.chars().count(),
+                item,
+                fuzzy_match.indexes,
+            ))
+        })
+        .collect();
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    matches
+        .into_iter()
+        .map(|(_, _, item, indexes)| (item.clone(), indexes))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, text: &str) -> i64 {
+        fuzzy_match(query, text).map_or(NO_MATCH, |fuzzy_match| fuzzy_match.score)
+    }
+
+    #[test]
+    fn ranks_prefixes_above_scattered_letters() {
+        assert!(score("mis", "mission.rs") > score("mis", "my_items.rs"));
+        let items = ["src/my_items.rs", "src/mission.rs", "src/lib.rs"];
+        let matches: Vec<&str> = fuzzy_filter(&items, "mis", |item| item)
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect();
+        assert_eq!(matches, vec!["src/mission.rs", "src/my_items.rs"]);
+    }
+
+    #[test]
+    fn ranks_matches_after_a_path_separator_higher() {
+        assert!(score("view", "src/views/app.rs") > score("view", "src/reviews/app.rs"));
+        assert!(score("app", "src/app.rs") > score("app", "src/wrapper.rs"));
+    }
+
+    #[test]
+    fn returns_the_indexes_to_highlight() {
+        assert_eq!(
+            fuzzy_match("msnview", "mission_view.rs").unwrap().indexes,
+            vec![0, 3, 6, 8, 9, 10, 11]
+        );
+        // words match anywhere and their indexes are merged in order
+        assert_eq!(
+            fuzzy_match("Mission VIEW", "src/views/mission.rs")
+                .unwrap()
+                .indexes,
+            vec![4, 5, 6, 7, 10, 11, 12, 13, 14, 15, 16]
+        );
+    }
+
+    #[test]
+    fn needs_every_letter_in_order() {
+        assert_eq!(fuzzy_match("nsm", "mission.rs"), None);
+        assert_eq!(fuzzy_match("mission view", "src/mission.rs"), None);
+        assert_eq!(fuzzy_match("", "src/mission.rs").unwrap().score, 0);
+    }
+}
@@ -0,0 +1,159 @@
+use std::process::Command;
+
+use anyhow::Result;
+
+/// Same as lilicore's `git_add_temporary_commit`, but only stages the given paths
+/// so unrelated changes in the working tree stay out of the execution commit.
+///
+/// Returns the hash of the new commit, None when the paths had no changes to commit.
+pub fn git_add_temporary_commit_paths(
+    project_dir: &str,
+    paths: &[String],
+    execution_id: Option<String>,
+) -> Result<Option<String>> {
+    if paths.is_empty() {
+        anyhow::bail!("No files to commit");
+    }
+    let now_timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    // git refuses pathspecs matching nothing, like a file created then deleted by the actions
+    let paths: Vec<String> = paths
+        .iter()
+        .filter(|path| git_path_exists(project_dir, path))
+        .cloned()
+        .collect();
+    if paths.is_empty() {
+        return Ok(None);
+    }
+    // -A also stages files that were deleted
+    run_git(project_dir, &["add", "-A", "--"], &paths)?;
+    // actions writing the content the files already had leave nothing to commit
+    if !git_has_staged_changes(project_dir, &paths)? {
+        return Ok(None);
+    }
+    let temp_message = match execution_id {
+        Some(execution_id) => format!("execution-{}", execution_id),
+        None => String::from(""),
+    };
+    let message = format!("chore(temp): {} {}", now_timestamp, temp_message);
+    run_git(project_dir, &["commit", "-m", &message, "--"], &paths)?;
+    git_head_hash(project_dir).map(Some)
+}
+
+// on disk, or tracked by git when the file was deleted
+fn git_path_exists(project_dir: &str, path: &str) -> bool {
+    std::path::Path::new(project_dir).join(path).exists()
+        || run_git(
+            project_dir,
+            &["ls-files", "--error-unmatch", "--"],
+            &[path.to_string()],
+        )
+        .is_ok()
+}
+
+fn git_has_staged_changes(project_dir: &str, paths: &[String]) -> Result<bool> {
+    let status = Command::new("git")
+        .args(["diff", "--cached", "--quiet", "--"])
+        .args(paths)
+        .current_dir(project_dir)
+        .status()?;
+    // --quiet exits with 1 when there are changes, and with other codes on errors
+    match status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => anyhow::bail!("git diff failed: {}", status),
+    }
+}
+
+pub fn git_head_hash(project_dir: &str) -> Result<String> {
+    run_git(project_dir, &["rev-parse", "HEAD"], &[])
+}
+
+/// A commit as listed in the commit picker.
+#[derive(Debug, Clone)]
+pub struct CommitSummary {
+    pub hash: String,
+    pub message: String,
+    pub author: String,
+    // relative, like "2 days ago"
+    pub date: String,
+    // like "3 files +10 -2"
+    pub stat: String,
+}
+
+/// Same as lilicore's `get_git_last_commits`, with the author, date and stat of each commit.
+pub fn git_last_commit_summaries(project_dir: &str, count: usize) -> Result<Vec<CommitSummary>> {
+    let count = format!("-n{}", count);
+    let output = run_git(
+        project_dir,
+        &[
+            "log",
+            &count,
+            "--shortstat",
+            "--format=%x1e%h%x1f%an%x1f%ar%x1f%s",
+        ],
+        &[],
+    )?;
+    let summaries = output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let mut fields = lines.next()?.split('\x1f');
+            let hash = fields.next()?.to_string();
+            let author = fields.next()?.to_string();
+            let date = fields.next()?.to_string();
+            let message = fields.next().unwrap_or_default().to_string();
+            // merges and empty commits have no stat line
+            let stat = lines
+                .map(|line| line.trim())
+                .find(|line| !line.is_empty())
+                .map(short_stat)
+                .unwrap_or_default();
+            Some(CommitSummary {
+                hash,
+                message,
+                author,
+                date,
+                stat,
+            })
+        })
+        .collect();
+    Ok(summaries)
+}
+
+// "3 files changed, 10 insertions(+), 2 deletions(-)" becomes "3 files +10 -2"
+fn short_stat(stat: &str) -> String {
+    stat.split(", ")
+        .filter_map(|part| {
+            let (number, kind) = part.split_once(' ')?;
+            match kind {
+                kind if kind.starts_with("file") => {
+                    Some(format!("{} {}", number, kind.split(' ').next()?))
+                }
+                kind if kind.starts_with("insertion") => Some(format!("+{}", number)),
+                kind if kind.starts_with("deletion") => Some(format!("-{}", number)),
+                _ => None,
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// returns the trimmed stdout of the command
+fn run_git(project_dir: &str, args: &[&str], paths: &[String]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .args(paths)
+        .current_dir(project_dir)
+        .output()?;
+    if !output.status.success() {
+        // some errors, like "nothing to commit", are printed on stdout
+        let message = match output.stderr.is_empty() {
+            true => String::from_utf8_lossy(&output.stdout),
+            false => String::from_utf8_lossy(&output.stderr),
+        };
+        anyhow::bail!("git {} failed: {}", args[0], message.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,116 @@
+use std::fmt::Display;
+
+use crate::context_commands::item_command;
+
+/// Lines of a file, 1-based and inclusive, e.g. `120-190`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    pub fn new(first: usize, second: usize) -> Self {
+        Self {
+            start: first.min(second),
+            end: first.max(second),
+        }
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        self.start <= line && line <= self.end
+    }
+}
+
+impl Display for LineRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.start == self.end {
+            true => write!(f, "{}", self.start),
+            false => write!(f, "{}-{}", self.start, self.end),
+        }
+    }
+}
+
+/// Splits a context item like `src/app.rs:120-190,200` into its path and line ranges.
+///
+/// Items without ranges are whole files, command items never have ranges.
+pub fn parse_context_item(item: &str) -> (&str, Vec<LineRange>) {
+    if item_command(item).is_some() {
+        return (item, vec![]);
+    }
+    let (path, ranges) = match item.rsplit_once(':') {
+        Some(split) => split,
+        None => return (item, vec![]),
+    };
+    let ranges: Option<Vec<LineRange>> = ranges
+        .split(',')
+        .map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let start = start.trim().parse().ok().filter(|start| *start > 0)?;
+            let end = end.trim().parse().ok().filter(|end| *end > 0)?;
+            Some(LineRange::new(start, end))
+        })
+        .collect();
+    match ranges {
+        Some(ranges) => (path, ranges),
+        // a colon that is part of the file name
+        None => (item, vec![]),
+    }
+}
+
+pub fn format_context_item(path: &str, ranges: &[LineRange]) -> String {
+    if ranges.is_empty() {
+        return path.to_string();
+    }
+    let ranges: Vec<String> = ranges.iter().map(|range| range.to_string()).collect();
+    format!("{}:{}", path, ranges.join(","))
+}
+
+/// Adds the range, merging it with the ranges it overlaps or touches.
+pub fn add_line_range(ranges: &[LineRange], range: LineRange) -> Vec<LineRange> {
+    let mut ranges = ranges.to_vec();
+    ranges.push(range);
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<LineRange> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end + 1 => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Returns the lines of the ranges prefixed with their line numbers, `...` marks skipped lines.
+pub fn slice_lines(content: &str, ranges: &[LineRange]) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let width = lines.len().to_string().len();
+    let mut sliced = vec![];
+    let mut next_line = 1;
+    for range in ranges {
+        if range.start > lines.len() {
+            break;
+        }
+        if range.start > next_line {
+            sliced.push(String::from("..."));
+        }
+        for number in range.start..=range.end.min(lines.len()) {
+            sliced.push(format!("{:>width$}| {}", number, lines[number - 1]));
+        }
+        next_line = range.end + 1;
+    }
+    if next_line <= lines.len() {
+        sliced.push(String::from("..."));
+    }
+    sliced.join("\n")
+}
+
+/// Reads a context item, only the lines of its ranges when it has any.
+pub fn read_context_item(project_dir: &str, item: &str) -> Option<String> {
+    let (path, ranges) = parse_context_item(item);
+    let content = std::fs::read_to_string(format!("{}/{}", project_dir, path)).ok()?;
+    match ranges.is_empty() {
+        true => Some(content),
+        false => Some(slice_lines(&content, &ranges)),
+    }
+}
@@ -1,6 +1,14 @@
-use lilicore::code_missions_api::{MissionAction, MissionActionType};
 use ratatui::widgets::ListItem;
 
+use crate::{
+    conflicts::FileConflict,
+    history::{format_age, HistoryEntry},
+    missions::{ActionItem, ActionType, ProposedAction},
+    presets::ContextPreset,
+    reduction::ReducedFile,
+    utils::llm_tokens::format_tokens,
+};
+
 #[derive(Debug, Clone, Default)]
 pub struct SelectableList<T: SelectableItem> {
     pub selected_index: Option<usize>,
@@ -23,15 +31,78 @@ impl SelectableItem for String {
     }
 }
 
-impl SelectableItem for MissionAction {
+impl SelectableItem for ProposedAction {
     fn to_string(&self) -> String {
         let path = &self.path;
-        let action_type = match self.action_type {
-            MissionActionType::CreateFile => "+",
-            MissionActionType::UpdateFile => "~",
-            // MissionActionType::DeleteFile => "D",
+        match self.action_type {
+            ActionType::CreateFile => format!("+ {}", path),
+            ActionType::UpdateFile => format!("~ {}", path),
+            ActionType::DeleteFile => format!("- {}", path),
+            ActionType::RenameFile => {
+                format!("→ {} → {}", path, self.new_path.clone().unwrap_or_default())
+            }
+        }
+    }
+}
+
+impl SelectableItem for ActionItem {
+    fn to_string(&self) -> String {
+        let checkbox = if self.checked { "[x]" } else { "[ ]" };
+        let rejected_hunks = match self.rejected_hunks.len() {
+            0 => String::new(),
+            1 => String::from(" (1 hunk rejected)"),
+            count => format!(" ({} hunks rejected)", count),
         };
-        format!("{} {}", action_type, path)
+        format!(
+            "{} {}{}",
+            checkbox,
+            SelectableItem::to_string(&self.action),
+            rejected_hunks
+        )
+    }
+}
+
+impl SelectableItem for HistoryEntry {
+    fn to_string(&self) -> String {
+        let message = self.message.lines().next().unwrap_or_default();
+        format!(
+            "{:<9} {:<10} {}",
+            format_age(self.timestamp),
+            self.outcome,
+            message
+        )
+    }
+}
+
+impl SelectableItem for ContextPreset {
+    fn to_string(&self) -> String {
+        format!(
+            "{:<9} {:>3} items  {}",
+            self.scope,
+            self.items.len(),
+            self.name
+        )
+    }
+}
+
+impl SelectableItem for ReducedFile {
+    fn to_string(&self) -> String {
+        format!(
+            "{:<12} {:>6} {}",
+            self.reduction,
+            format_tokens(self.tokens),
+            self.path
+        )
+    }
+}
+
+impl SelectableItem for FileConflict {
+    fn to_string(&self) -> String {
+        match self.action_index {
+            Some(_) => format!("{:<9} {}", self.resolution, self.path),
+            // context files are only read, so they can not be skipped or merged
+            None => format!("{:<9} {} (context)", "Proceed", self.path),
+        }
     }
 }
 
@@ -77,6 +148,13 @@ where
         }
     }
 
+    pub fn get_selected_item_mut(&mut self) -> Option<&mut T> {
+        match self.selected_index {
+            Some(index) => self.items.get_mut(index),
+            None => None,
+        }
+    }
+
     pub fn to_items(&self) -> Vec<ListItem> {
         self.items
             .iter()
@@ -92,10 +170,6 @@ where
         self.items.push(item);
     }
 
-    pub fn set_items(&mut self, items: Vec<T>) {
-        self.items = items;
-    }
-
     pub fn remove_item_at(&mut self, index: usize) {
         self.items.remove(index);
     }
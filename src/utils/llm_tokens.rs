@@ -1,8 +1,118 @@
-pub fn llm_tokens_count_string(text: &str) -> usize {
-    use tiktoken_rs::p50k_base;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
 
-    let bpe = p50k_base().unwrap();
-    let tokens = bpe.encode_with_special_tokens(text);
-    // println!("Token count: {}", tokens.len());
-    tokens.len()
+use tiktoken_rs::{
+    get_bpe_from_tokenizer,
+    model::get_context_size,
+    tokenizer::{get_tokenizer, Tokenizer},
+    CoreBPE,
+};
+
+use crate::context_commands::item_command;
+
+use super::line_ranges::{parse_context_item, read_context_item};
+
+// loading a tokenizer takes a while, so each one is only loaded once
+static TOKENIZERS: OnceLock<Mutex<HashMap<Tokenizer, Arc<CoreBPE>>>> = OnceLock::new();
+
+fn load_tokenizer(tokenizer: Tokenizer) -> Arc<CoreBPE> {
+    let mut tokenizers = TOKENIZERS.get_or_init(Default::default).lock().unwrap();
+    tokenizers
+        .entry(tokenizer)
+        .or_insert_with(|| Arc::new(get_bpe_from_tokenizer(tokenizer).unwrap()))
+        .clone()
+}
+
+/// Counts tokens the way the model missions are sent to does.
+#[derive(Clone)]
+pub struct TokenCounter {
+    pub model: String,
+    // the context window of the model
+    pub limit: usize,
+    bpe: Arc<CoreBPE>,
+    // by context item, recounted when the file is modified
+    file_counts: HashMap<String, (SystemTime, usize)>,
+    // by command item, recounted when the output changes
+    output_counts: HashMap<String, (u64, usize)>,
+}
+
+impl Debug for TokenCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenCounter")
+            .field("model", &self.model)
+            .field("limit", &self.limit)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenCounter {
+    /// Uses the given limit instead of the context window of the model, when set.
+    pub fn new(model: &str, limit: Option<usize>) -> Self {
+        // models tiktoken does not know, like most local ones, are counted like gpt-4
+        let tokenizer = get_tokenizer(model).unwrap_or(Tokenizer::Cl100kBase);
+        Self {
+            model: model.to_string(),
+            limit: limit.unwrap_or_else(|| get_context_size(model)),
+            bpe: load_tokenizer(tokenizer),
+            file_counts: HashMap::new(),
+            output_counts: HashMap::new(),
+        }
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+
+    /// Counts the tokens of a context item as sent, None when its file can not be read.
+    ///
+    /// The output of command items is counted instead of a file.
+    pub fn count_context_item(
+        &mut self,
+        project_dir: &str,
+        item: &str,
+        output: &str,
+    ) -> Option<usize> {
+        if item_command(item).is_some() {
+            return Some(self.count_output(item, output));
+        }
+        let (path, _) = parse_context_item(item);
+        let full_path = format!("{}/{}", project_dir, path);
+        let modified = std::fs::metadata(full_path).ok()?.modified().ok()?;
+        if let Some((counted_modified, count)) = self.file_counts.get(item) {
+            if *counted_modified == modified {
+                return Some(*count);
+            }
+        }
+        let content = read_context_item(project_dir, item)?;
+        let count = self.count(&content);
+        self.file_counts.insert(item.to_string(), (modified, count));
+        Some(count)
+    }
+
+    fn count_output(&mut self, item: &str, output: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        output.hash(&mut hasher);
+        let hash = hasher.finish();
+        if let Some((counted_hash, count)) = self.output_counts.get(item) {
+            if *counted_hash == hash {
+                return *count;
+            }
+        }
+        let count = self.count(output);
+        self.output_counts.insert(item.to_string(), (hash, count));
+        count
+    }
+}
+
+/// Formats a token count in a few characters, e.g. `850` or `12.3k`.
+pub fn format_tokens(count: usize) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        _ => format!("{:.1}k", count as f64 / 1000.0),
+    }
 }
@@ -1,2 +1,7 @@
+pub mod diff;
+pub mod editor;
+pub mod fuzzy;
+pub mod git;
+pub mod line_ranges;
 pub mod list;
 pub mod llm_tokens;
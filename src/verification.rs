@@ -0,0 +1,179 @@
+use std::{process::Stdio, sync::Arc};
+
+use anyhow::Result;
+use lilicore::{code_missions_api::SetFailRequest, git_repo::git_undo_last_commit};
+use strum::Display;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    sync::mpsc::UnboundedSender,
+    task::AbortHandle,
+};
+
+use crate::{
+    backend::MissionBackend,
+    events::{AppEvent, VerificationEvent},
+    utils::git::git_head_hash,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum VerificationStatus {
+    // the commands changed since the user last agreed to run them
+    #[strum(serialize = "Run the commands?")]
+    Confirming,
+    Running,
+    Passed,
+    Failed,
+    #[strum(serialize = "Rolled back")]
+    RolledBack,
+}
+
+/// The verify commands of the project running on an applied mission.
+#[derive(Debug, Clone)]
+pub struct Verification {
+    pub execution_id: String,
+    pub commit_hash: String,
+    pub rollback_on_failure: bool,
+    pub commands: Vec<String>,
+    pub status: VerificationStatus,
+    // every command starts with a `$ command` line
+    pub output: Vec<String>,
+    // lines scrolled up from the end of the output
+    pub scroll_back: usize,
+    abort_handle: Option<Arc<AbortHandle>>,
+}
+
+impl Verification {
+    /// Lists the commands and waits for the user to confirm, nothing runs yet.
+    pub fn confirm(
+        commands: Vec<String>,
+        execution_id: String,
+        commit_hash: String,
+        rollback_on_failure: bool,
+    ) -> Self {
+        let mut output = vec![String::from(
+            "The project verifies missions with these commands:",
+        )];
+        output.extend(commands.iter().map(|command| format!("$ {}", command)));
+        output.push(String::new());
+        output.push(String::from("Run them? (y/n)"));
+        Self {
+            execution_id,
+            commit_hash,
+            rollback_on_failure,
+            commands,
+            status: VerificationStatus::Confirming,
+            output,
+            scroll_back: 0,
+            abort_handle: None,
+        }
+    }
+
+    /// Runs the commands in the background, the output comes back as `AppEvent::Verification`.
+    pub fn start(
+        project_dir: &str,
+        commands: Vec<String>,
+        execution_id: String,
+        commit_hash: String,
+        rollback_on_failure: bool,
+        sender: UnboundedSender<AppEvent>,
+    ) -> Self {
+        let project_dir = project_dir.to_string();
+        let task_commands = commands.clone();
+        let task = tokio::spawn(async move {
+            let passed = run_verify_commands(&project_dir, &task_commands, |line| {
+                sender
+                    .send(AppEvent::Verification(VerificationEvent::Output(line)))
+                    .ok();
+            })
+            .await;
+            sender
+                .send(AppEvent::Verification(VerificationEvent::Finished(passed)))
+                .ok();
+        });
+        Self {
+            execution_id,
+            commit_hash,
+            rollback_on_failure,
+            commands,
+            status: VerificationStatus::Running,
+            output: vec![],
+            scroll_back: 0,
+            abort_handle: Some(Arc::new(task.abort_handle())),
+        }
+    }
+
+    /// Stops the running command, it is killed with the task.
+    pub fn abort(&self) {
+        if let Some(abort_handle) = &self.abort_handle {
+            abort_handle.abort();
+        }
+    }
+}
+
+/// Runs the commands one after the other in the project dir and stops at the first failure.
+///
+/// Every output line is passed to `on_output`, stdout and stderr interleaved.
+pub async fn run_verify_commands(
+    project_dir: &str,
+    commands: &[String],
+    mut on_output: impl FnMut(String),
+) -> bool {
+    for command in commands {
+        on_output(format!("$ {}", command));
+        match run_verify_command(project_dir, command, &mut on_output).await {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(err) => {
+                on_output(format!("Failed to run the command: {}", err));
+                return false;
+            }
+        }
+    }
+    true
+}
+
+async fn run_verify_command(
+    project_dir: &str,
+    command: &str,
+    on_output: &mut impl FnMut(String),
+) -> Result<bool> {
+    // stderr is redirected by the shell so both streams keep their order
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(format!("exec 2>&1\n{}", command))
+        .current_dir(project_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).split(b'\n');
+        while let Some(line) = lines.next_segment().await? {
+            let line = String::from_utf8_lossy(&line);
+            on_output(line.trim_end_matches('\r').to_string());
+        }
+    }
+    Ok(child.wait().await?.success())
+}
+
+/// Undoes the commit of a mission that failed verification and fails its execution.
+pub async fn rollback_mission(
+    backend: &dyn MissionBackend,
+    project_dir: &str,
+    execution_id: &str,
+    commit_hash: &str,
+) -> Result<()> {
+    // a commit made after the mission must not be the one undone
+    if git_head_hash(project_dir)? != commit_hash {
+        anyhow::bail!("The last commit is not the mission anymore, nothing was rolled back");
+    }
+    git_undo_last_commit(project_dir)?;
+    let req_fail = SetFailRequest {
+        execution_id: execution_id.to_string(),
+    };
+    if let Err(err) = backend.set_fail(req_fail).await {
+        anyhow::bail!(err.message);
+    }
+    Ok(())
+}
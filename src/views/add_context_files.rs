@@ -2,11 +2,13 @@ use std::{collections::HashMap, sync::Mutex};
 
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent};
+use globset::GlobBuilder;
 use lilicore::git_repo::get_git_commit_files;
 use ratatui::{
     prelude::{Backend, Constraint, Layout, Rect},
-    text::Line,
-    widgets::{Block, Borders, ListItem, ListState, Paragraph},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, ListItem, ListState, Paragraph},
     Frame,
 };
 
@@ -18,11 +20,12 @@ use crate::{
         text_input::TextInputComponent,
         AppComponent,
     },
+    context_commands::{command_item, item_command},
     shortcuts::{handle_text_input_event, ShortcutHandlerResponse},
-    utils::list::SelectableList,
+    utils::{fuzzy::fuzzy_filter, list::SelectableList, llm_tokens::format_tokens},
 };
 
-use super::AppViewTrait;
+use super::{run_context_command, AppViewTrait};
 
 #[derive(Debug, Clone)]
 pub enum SearchableListType {
@@ -33,6 +36,8 @@ pub enum SearchableListType {
 pub struct AddContextFilesView {
     selected_items: Vec<String>,
     cursor_index: usize,
+    // files of a directory or glob waiting for the user to confirm they are added
+    pending_files: Option<Vec<String>>,
 }
 
 impl AddContextFilesView {
@@ -40,14 +45,17 @@ impl AddContextFilesView {
         Self {
             selected_items: vec![],
             cursor_index: 0,
+            pending_files: None,
         }
     }
 
     fn get_filtered_list(&mut self, state: &mut AppState) -> SelectableList<(String, String)> {
         let query_value = state.get_input_value_from_focused(FocusedBlock::SearchContextFileInput);
-        state
-            .searchable_list
-            .filter_and_collect(|item| item.0.contains(&query_value))
+        let items = search_items(state, &query_value)
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect();
+        SelectableList::new(state.searchable_list.selected_index, items)
     }
 
     fn get_selected_context_file_paths(&mut self, state: &mut AppState) -> Vec<String> {
@@ -64,9 +72,7 @@ impl AddContextFilesView {
     //     orig_project_files[self.cursor_index.clone()].clone()
     // }
 
-    fn add_picked_item_to_context(&mut self, state: &mut AppState) {
-        // todo: get file content (or maybe remove file content and get on the fly)
-        // let selected_item = self.get_selected_item(state);
+    fn get_cursor_item(&mut self, state: &mut AppState) -> Option<(String, String)> {
         let mut filtered_list = self.get_filtered_list(state);
         let list_len = filtered_list.len();
         let real_index = if &list_len > &0 {
@@ -75,64 +81,120 @@ impl AddContextFilesView {
             None
         };
         filtered_list.select(real_index);
-        let default_item = (String::new(), String::new());
-        let selected_item = filtered_list
-            .get_selected_item()
-            .unwrap_or(&default_item)
-            .clone();
+        filtered_list.get_selected_item().cloned()
+    }
+
+    fn add_picked_item_to_context(&mut self, state: &mut AppState) {
+        // todo: get file content (or maybe remove file content and get on the fly)
+        // let selected_item = self.get_selected_item(state);
+        let query_value = state.get_input_value_from_focused(FocusedBlock::SearchContextFileInput);
+        if is_glob(&state.searchable_list_type, &query_value) {
+            let files = self
+                .get_filtered_list(state)
+                .items
+                .into_iter()
+                .map(|(path, _)| path)
+                .filter(|path| !is_directory_item(path))
+                .collect();
+            self.ask_to_add_files(state, files);
+            return;
+        }
+        // nothing matches the search
+        let selected_item = match self.get_cursor_item(state) {
+            Some(selected_item) => selected_item,
+            None => return,
+        };
+        if is_directory_item(&selected_item.0) {
+            let files = state
+                .searchable_list
+                .items
+                .iter()
+                .map(|(path, _)| path.clone())
+                .filter(|path| path.starts_with(&selected_item.0) && !is_directory_item(path))
+                .collect();
+            self.ask_to_add_files(state, files);
+            return;
+        }
         let is_selected = self.selected_items.contains(&selected_item.0);
         if is_selected {
             return;
         }
         self.selected_items.push(selected_item.0.clone());
-        match state.searchable_list_type {
-            SearchableListType::ProjectFiles => {
-                state
-                    .context_items
-                    .items
-                    .retain(|(p, _)| p != &selected_item.0);
-                // self.selected_items.retain(|p| p != &selected_item.0);
-                state.context_items.add_item(selected_item);
+        state
+            .context_items
+            .items
+            .retain(|(p, _)| p != &selected_item.0);
+        // self.selected_items.retain(|p| p != &selected_item.0);
+        state.context_items.add_item(selected_item);
+    }
+
+    // the files are only added once the user confirms, there may be a lot of them
+    fn ask_to_add_files(&mut self, state: &mut AppState, files: Vec<String>) {
+        let files: Vec<String> = files
+            .into_iter()
+            .filter(|path| !state.context_items.items.iter().any(|(p, _)| p == path))
+            .collect();
+        if files.is_empty() {
+            state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+                "No files to add, they are all in the context already",
+            )));
+            return;
+        }
+        self.pending_files = Some(files);
+    }
+
+    fn add_pending_files(&mut self, state: &mut AppState) {
+        let files = match self.pending_files.take() {
+            Some(files) => files,
+            None => return,
+        };
+        let count = files.len();
+        for path in files {
+            self.selected_items.push(path.clone());
+            state.context_items.add_item((path, String::new()));
+        }
+        state.set_header_status(HeaderStatus::SuccessMessage(format!(
+            "{} files added to the context",
+            count
+        )));
+    }
+
+    // marks the commit under the cursor, or unmarks it
+    fn toggle_picked_commit(&mut self, state: &mut AppState) {
+        let selected_item = match self.get_cursor_item(state) {
+            Some(selected_item) => selected_item,
+            None => return,
+        };
+        match self.selected_items.contains(&selected_item.0) {
+            true => self.selected_items.retain(|p| p != &selected_item.0),
+            false => self.selected_items.push(selected_item.0),
+        }
+    }
+
+    // adds the marked commits, or the one under the cursor when none are marked
+    fn add_picked_commits_to_context(&mut self, state: &mut AppState, as_patch: bool) {
+        let mut picked_items = self.selected_items.clone();
+        if picked_items.is_empty() {
+            if let Some(selected_item) = self.get_cursor_item(state) {
+                picked_items.push(selected_item.0);
             }
-            SearchableListType::GitCommits => {
-                // state.context_items.add_item(selected_item);
-                let commit_hash = selected_item.0.split(" ").next().unwrap_or("");
-                if commit_hash.is_empty() {
-                    return;
-                }
-                let commit_files = match get_git_commit_files(commit_hash, &state.project_dir) {
-                    Ok(commit_files) => commit_files,
-                    Err(e) => {
-                        state.set_header_status(HeaderStatus::ErrorMessage(e.to_string()));
-                        return;
-                    }
-                };
-                let project_files = match state.get_project_files() {
-                    Ok(project_files) => project_files,
-                    Err(e) => {
-                        state.set_header_status(HeaderStatus::ErrorMessage(format!(
-                            "unable to get project_files: {}",
-                            e.to_string()
-                        )));
-                        return;
-                    }
-                };
-                for commit_file in commit_files {
-                    let path = commit_file.path;
-                    // let project_dir = state.project_dir.clone();
-                    // let full_path = format!("{}/{}", project_dir, path);
-                    // match std::path::Path::new(&full_path).exists() {
-                    match project_files.contains(&path) {
-                        true => {
-                            let item = (path, String::from(""));
-                            state.context_items.items.retain(|(p, _)| p != &item.0);
-                            state.context_items.add_item(item);
-                        }
-                        false => {}
-                    };
+        }
+        for picked_item in picked_items {
+            if item_command(&picked_item).is_some() {
+                add_command_item(state, picked_item);
+                continue;
+            }
+            let commit_hash = picked_item.split(" ").next().unwrap_or("");
+            if commit_hash.is_empty() {
+                continue;
+            }
+            match as_patch {
+                true => {
+                    add_command_item(state, command_item(&format!("git show {}", commit_hash)))
                 }
+                false => add_commit_files(state, commit_hash),
             }
-        };
+        }
     }
 
     pub async fn handle_events(
@@ -140,6 +202,14 @@ impl AddContextFilesView {
         state: &mut AppState,
         key: &KeyEvent,
     ) -> Result<ShortcutHandlerResponse> {
+        if self.pending_files.is_some() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => self.add_pending_files(state),
+                KeyCode::Char('n') | KeyCode::Esc => self.pending_files = None,
+                _ => {}
+            }
+            return Ok(ShortcutHandlerResponse::StopPropagation);
+        }
         if KeyCode::Up == key.code {
             self.cursor_index = self.cursor_index.saturating_sub(1);
             return Ok(ShortcutHandlerResponse::StopPropagation);
@@ -148,6 +218,20 @@ impl AddContextFilesView {
             self.cursor_index = self.cursor_index.saturating_add(1);
             return Ok(ShortcutHandlerResponse::StopPropagation);
         }
+        if let SearchableListType::GitCommits = state.searchable_list_type {
+            match key.code {
+                KeyCode::Char(' ') => {
+                    self.toggle_picked_commit(state);
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Enter => self.add_picked_commits_to_context(state, false),
+                KeyCode::Tab => {
+                    self.add_picked_commits_to_context(state, true);
+                    return Ok(self.close(state));
+                }
+                _ => {}
+            }
+        }
         // add to context files
         if KeyCode::Char(' ') == key.code {
             self.add_picked_item_to_context(state);
@@ -155,15 +239,133 @@ impl AddContextFilesView {
         }
         // exit this component and reset
         if KeyCode::Enter == key.code || KeyCode::Esc == key.code {
-            self.cursor_index = 0;
-            // orig_project_files = state.get_project_files()?;
-            state.set_screen(AppScreen::Mission);
-            state.set_focused_block(FocusedBlock::ContextFiles);
-            return Ok(ShortcutHandlerResponse::StopPropagation);
+            return Ok(self.close(state));
         }
         self.cursor_index = 0;
         return handle_text_input_event(state, key, &FocusedBlock::SearchContextFileInput);
     }
+
+    fn close(&mut self, state: &mut AppState) -> ShortcutHandlerResponse {
+        self.cursor_index = 0;
+        // marked commits not added with Enter or Tab are dropped
+        self.selected_items.clear();
+        // orig_project_files = state.get_project_files()?;
+        state.set_screen(AppScreen::Mission);
+        state.set_focused_block(FocusedBlock::ContextFiles);
+        ShortcutHandlerResponse::StopPropagation
+    }
+}
+
+fn add_command_item(state: &mut AppState, item: String) {
+    if !state.context_items.items.iter().any(|(p, _)| p == &item) {
+        state.context_items.add_item((item.clone(), String::new()));
+    }
+    run_context_command(state, &item);
+}
+
+/// Returns the project files with an entry for each of their directories, like `src/views/`,
+/// listed before the files in it.
+pub fn project_picker_items(project_files: Vec<String>) -> Vec<(String, String)> {
+    let mut directories: Vec<String> = project_files
+        .iter()
+        .flat_map(|path| {
+            path.match_indices('/')
+                .map(|(index, _)| path[..=index].to_string())
+                .collect::<Vec<String>>()
+        })
+        .collect();
+    directories.sort();
+    directories.dedup();
+    let mut items: Vec<String> = directories.into_iter().chain(project_files).collect();
+    items.sort();
+    items
+        .into_iter()
+        .map(|item| (item, String::from("")))
+        .collect()
+}
+
+// splits the text in spans, the matched characters in bold yellow
+fn highlight_matches(text: &str, indexes: &[usize], style: Style) -> Vec<Span<'static>> {
+    let matched_style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut spans: Vec<Span> = vec![];
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (index, c) in text.chars().enumerate() {
+        let matched = indexes.contains(&index);
+        if matched != run_matched && !run.is_empty() {
+            let run_style = if run_matched { matched_style } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    let run_style = if run_matched { matched_style } else { style };
+    spans.push(Span::styled(run, run_style));
+    spans
+}
+
+fn is_directory_item(item: &str) -> bool {
+    item.ends_with('/')
+}
+
+// only project files are searched with globs, commit messages often have `[` or `{`
+fn is_glob(list_type: &SearchableListType, query: &str) -> bool {
+    matches!(list_type, SearchableListType::ProjectFiles) && query.contains(['*', '?', '[', '{'])
+}
+
+// the items of the list matching the query, the best first, with the indexes of their matched
+// characters
+fn search_items(state: &AppState, query: &str) -> Vec<((String, String), Vec<usize>)> {
+    let items = &state.searchable_list.items;
+    if !is_glob(&state.searchable_list_type, query) {
+        return fuzzy_filter(items, query, |(item, _)| item);
+    }
+    // a glob matches whole paths, `*` stays in one directory and `**` crosses them. An invalid
+    // glob, like one still being typed, matches nothing
+    let matcher = match GlobBuilder::new(query).literal_separator(true).build() {
+        Ok(glob) => glob.compile_matcher(),
+        Err(_) => return vec![],
+    };
+    items
+        .iter()
+        .filter(|(item, _)| !is_directory_item(item) && matcher.is_match(item))
+        .map(|item| (item.clone(), vec![]))
+        .collect()
+}
+
+// adds the files of the commit that still exist
+fn add_commit_files(state: &mut AppState, commit_hash: &str) {
+    let commit_files = match get_git_commit_files(commit_hash, &state.project_dir) {
+        Ok(commit_files) => commit_files,
+        Err(e) => {
+            state.set_header_status(HeaderStatus::ErrorMessage(e.to_string()));
+            return;
+        }
+    };
+    let project_files = match state.get_project_files() {
+        Ok(project_files) => project_files,
+        Err(e) => {
+            state.set_header_status(HeaderStatus::ErrorMessage(format!(
+                "unable to get project_files: {}",
+                e.to_string()
+            )));
+            return;
+        }
+    };
+    for commit_file in commit_files {
+        let path = commit_file.path;
+        // let project_dir = state.project_dir.clone();
+        // let full_path = format!("{}/{}", project_dir, path);
+        // match std::path::Path::new(&full_path).exists() {
+        match project_files.contains(&path) {
+            true => {
+                let item = (path, String::from(""));
+                state.context_items.items.retain(|(p, _)| p != &item.0);
+                state.context_items.add_item(item);
+            }
+            false => {}
+        };
+    }
 }
 
 impl AppViewTrait for AddContextFilesView {
@@ -227,9 +429,11 @@ impl AppViewTrait for AddContextFilesView {
 
         // let project_files = SelectableList::new(orig_project_files.clone());
         // let project_files = self.list.clone();
-        let searchable_items = state
-            .searchable_list
-            .filter_and_collect(|item| item.0.contains(&filter_string));
+        let found_items = search_items(state, &filter_string);
+        let searchable_items = SelectableList::new(
+            None,
+            found_items.iter().map(|(item, _)| item.clone()).collect(),
+        );
         // let mut searchable_items = state.searchable_list.items.clone();
         // let context_items = state.context_items.to_items();
 
@@ -255,31 +459,43 @@ impl AppViewTrait for AddContextFilesView {
             }
         }
 
-        let drawable_items = searchable_items
-            .to_items()
+        let is_commit_list = matches!(state.searchable_list_type, SearchableListType::GitCommits);
+        let drawable_items = found_items
             .iter()
-            .enumerate()
-            .map(|(i, item)| {
-                let is_in_selected_items = self
-                    .selected_items
-                    .contains(&searchable_items.items[i].0.clone());
-                let is_in_context = state
-                    .context_items
-                    .items
-                    .iter()
-                    .any(|(p, _)| p == &searchable_items.items[i].0.clone());
-                if is_in_selected_items || is_in_context {
-                    item.clone()
-                        .style(ratatui::style::Style::default().fg(ratatui::style::Color::Green))
-                } else {
-                    item.clone()
-                }
+            .map(|((name, details), matched_indexes)| {
+                let is_in_selected_items = self.selected_items.contains(name);
+                let is_in_context = state.context_items.items.iter().any(|(p, _)| p == name);
+                // commits are only marked until Enter or Tab adds them
+                let color = match (is_in_selected_items, is_in_context) {
+                    (true, _) if is_commit_list => Some(ratatui::style::Color::Yellow),
+                    (true, _) | (false, true) => Some(ratatui::style::Color::Green),
+                    _ => None,
+                };
+                let style = match color {
+                    Some(color) => ratatui::style::Style::default().fg(color),
+                    None => ratatui::style::Style::default(),
+                };
+                // the author, date and stat of commits
+                let details = match details.is_empty() {
+                    true => String::new(),
+                    false => format!("  {}", details),
+                };
+                let mut spans = highlight_matches(name, matched_indexes, style);
+                spans.push(Span::styled(
+                    details,
+                    ratatui::style::Style::default().fg(ratatui::style::Color::DarkGray),
+                ));
+                ListItem::new(Line::from(spans))
             })
             .collect::<Vec<ListItem<'_>>>();
 
+        let title = match is_commit_list {
+            true => "Diffs and Commits",
+            false => "Project Files",
+        };
         let block = Block::default()
             .borders(Borders::ALL)
-            .title("Project Files")
+            .title(title)
             .border_style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
 
         let list = ratatui::widgets::List::new(drawable_items)
@@ -291,6 +507,37 @@ impl AppViewTrait for AddContextFilesView {
 
         frame.render_stateful_widget(list, list_rect, list_state);
 
+        if let Some(files) = &self.pending_files {
+            let project_dir = state.project_dir.clone();
+            let tokens: usize = files
+                .iter()
+                .filter_map(|path| {
+                    state
+                        .token_counter
+                        .count_context_item(&project_dir, path, "")
+                })
+                .sum();
+            let confirm_rect = Rect {
+                height: list_rect.height.min(3),
+                ..list_rect
+            };
+            let confirm = Paragraph::new(format!(
+                "Add {} files, about {} tokens? (y/n)",
+                files.len(),
+                format_tokens(tokens)
+            ))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Add files")
+                    .border_style(
+                        ratatui::style::Style::default().fg(ratatui::style::Color::Yellow),
+                    ),
+            );
+            frame.render_widget(Clear, confirm_rect);
+            frame.render_widget(confirm, confirm_rect);
+        }
+
         // let lines: Vec<Line> = project_files
         //     .iter()
         //     .map(|file| Line::from(file.to_string()))
@@ -109,9 +109,9 @@ fn _replace_context_files_with_actions(state: &mut AppState) -> Result<()> {
         .action_items
         .items
         .iter()
-        .map(|action| {
-            let path = action.path.clone();
-            let content = action.content.clone();
+        .map(|item| {
+            let path = item.action.path.clone();
+            let content = item.action.content.clone().unwrap_or_default();
             (path, content)
         })
         .collect::<Vec<(String, String)>>();
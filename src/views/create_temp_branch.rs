@@ -20,7 +20,6 @@ use crate::{
         shortcuts::ShortcutsComponent,
         AppComponent,
     },
-    redraw_app,
     shortcuts::ShortcutHandlerResponse,
 };
 
@@ -45,7 +44,18 @@ impl CreateTempBranchView {
                 if current_branch_name.clone().starts_with("temp-") {
                     anyhow::bail!("You are already on a temporary branch");
                 }
-                state.set_base_branch_name(&current_branch_name)?;
+                let base_branch_name = match state.requested_base_branch.clone() {
+                    Some(base_branch_name) => base_branch_name,
+                    None => current_branch_name.clone(),
+                };
+                if base_branch_name != current_branch_name {
+                    // the temporary branch starts from the requested base branch
+                    if let Err(err) = git_checkout(&base_branch_name, &state.project_dir) {
+                        state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                }
+                state.set_base_branch_name(&base_branch_name)?;
                 // create temporary branch
                 match git_temporary_branch_create(&state.project_dir) {
                     Ok(output) => {
@@ -83,6 +93,18 @@ fn _run_shell_command(command: &str, project_dir: &str) -> Result<String> {
     Ok(res.stdout)
 }
 
+// git prints progress to stderr, so only the exit status tells if the checkout failed
+fn git_checkout(branch_name: &str, project_dir: &str) -> Result<()> {
+    let output = std::process::Command::new("git")
+        .args(["checkout", branch_name])
+        .current_dir(project_dir)
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
 impl AppViewTrait for CreateTempBranchView {
     fn components(&mut self, state: &mut AppState) -> Result<HashMap<String, Mutex<AppComponent>>> {
         let el_header = HeaderComponent::new()?;
@@ -125,6 +147,13 @@ impl AppViewTrait for CreateTempBranchView {
 
         let project_dir = state.project_dir.clone();
         let current_branch = get_current_branch_name(&project_dir)?;
+        let branch_info = match &state.requested_base_branch {
+            Some(base_branch) if base_branch != &current_branch => format!(
+                "current branch: {}, base branch: {}",
+                current_branch, base_branch
+            ),
+            _ => format!("current branch: {}", current_branch),
+        };
 
         let line_contents: Vec<(Rect, Line)> = vec![
             (
@@ -153,7 +182,7 @@ impl AppViewTrait for CreateTempBranchView {
             ),
             (
                 bottom_line_rect,
-                Line::from(Span::raw(format!("current branch: {}", current_branch))),
+                Line::from(Span::raw(branch_info)),
             ),
         ];
 
@@ -0,0 +1,166 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::{Backend, Constraint, Direction, Layout, Rect},
+    Frame,
+};
+
+use crate::{
+    app::{AppScreen, AppState, FocusedBlock},
+    components::{
+        header::{HeaderComponent, HeaderStatus},
+        history::{history_details::HistoryDetailsComponent, history_list::HistoryListComponent},
+        shortcuts::ShortcutsComponent,
+        text_input::TextInputComponent,
+        AppComponent,
+    },
+    context_commands::item_command,
+    history::HistoryEntry,
+    shortcuts::{handle_text_input_event, ShortcutHandlerResponse},
+};
+
+use super::{run_context_command, send_mission_message, AppViewTrait};
+
+pub struct HistoryView;
+
+impl HistoryView {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub async fn handle_events(
+        &mut self,
+        state: &mut AppState,
+        key: &KeyEvent,
+    ) -> Result<ShortcutHandlerResponse> {
+        let mut entries = state.filtered_history_entries();
+        match key.code {
+            KeyCode::Up => {
+                entries.select_previous();
+                state.history_entries.select(entries.selected_index);
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            KeyCode::Down => {
+                entries.select_next();
+                state.history_entries.select(entries.selected_index);
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            KeyCode::Esc => {
+                state.set_screen(AppScreen::Mission);
+                state.set_focused_block(FocusedBlock::Home);
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            KeyCode::Enter => {
+                if let Some(entry) = entries.get_selected_item() {
+                    load_history_entry(state, entry);
+                    state.set_focused_block(FocusedBlock::Message);
+                    state.set_header_status(HeaderStatus::SuccessMessage(String::from(
+                        "Mission loaded from history",
+                    )));
+                }
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some(entry) = entries.get_selected_item() {
+                    load_history_entry(state, entry);
+                    state.set_focused_block(FocusedBlock::Home);
+                    return send_mission_message(state, entry.context_paths.is_empty());
+                }
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            _ => {}
+        }
+        let response = handle_text_input_event(state, key, &FocusedBlock::SearchHistoryInput)?;
+        // the filter changed, so the old index points somewhere else
+        state.history_entries.select(Some(0));
+        Ok(response)
+    }
+}
+
+// puts the message and context files of the entry back into the Mission screen
+fn load_history_entry(state: &mut AppState, entry: &HistoryEntry) {
+    state.set_input_value(&FocusedBlock::Message, &entry.message);
+    state.set_context_items(
+        entry
+            .context_paths
+            .iter()
+            .map(|path| (path.as_str(), ""))
+            .collect(),
+    );
+    // the output of command items is not kept in the history, the commands run again
+    for path in entry.context_paths.iter() {
+        if item_command(path).is_some() {
+            run_context_command(state, path);
+        }
+    }
+    state.set_screen(AppScreen::Mission);
+}
+
+impl AppViewTrait for HistoryView {
+    fn components(
+        &mut self,
+        _state: &mut AppState,
+    ) -> Result<HashMap<String, Mutex<AppComponent>>> {
+        let el_header = HeaderComponent::new()?;
+        let el_shortcuts = ShortcutsComponent::new()?;
+        let el_search =
+            TextInputComponent::new("search history", FocusedBlock::SearchHistoryInput)?;
+        let el_list = HistoryListComponent::new()?;
+        let el_details = HistoryDetailsComponent::new()?;
+
+        let mut components = HashMap::new();
+        components.insert(String::from("header"), el_header.as_mutex());
+        components.insert(String::from("shortcuts"), el_shortcuts.as_mutex());
+        components.insert(String::from("search"), el_search.as_mutex());
+        components.insert(String::from("list"), el_list.as_mutex());
+        components.insert(String::from("details"), el_details.as_mutex());
+
+        Ok(components)
+    }
+
+    fn positions<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        _state: &mut AppState,
+    ) -> Result<HashMap<String, Rect>> {
+        let [top_rect, main_rect, bottom_rect] = *Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(frame.size())
+        else {
+            return Ok(HashMap::new());
+        };
+
+        let [left_rect, details_rect] = *Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+            .split(main_rect)
+        else {
+            return Ok(HashMap::new());
+        };
+
+        let [search_rect, list_rect] = *Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(3)])
+            .split(left_rect)
+        else {
+            return Ok(HashMap::new());
+        };
+
+        let positions = vec![
+            (String::from("header"), top_rect),
+            (String::from("shortcuts"), bottom_rect),
+            (String::from("search"), search_rect),
+            (String::from("list"), list_rect),
+            (String::from("details"), details_rect),
+        ];
+
+        Ok(positions.into_iter().collect())
+    }
+}
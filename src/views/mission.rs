@@ -3,37 +3,56 @@ use std::{collections::HashMap, sync::Mutex};
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent};
 use lilicore::{
-    code_analyst,
     code_missions_api::{
-        self, set_approved, set_fail, ApiError, CodeMissionStatus, CreateMissionRequest,
-        CreateMissionResponse, ExecuteMissionRequest, MissionData, MissionExecution,
-        MissionExecutionContextFile, MissionExecutionStatus, SetApprovedRequest, SetFailRequest,
+        MissionData, MissionExecutionContextFile, ReviewActionsRequest, SetApprovedRequest,
+        SetFailRequest,
     },
     coder,
-    git_repo::{
-        get_current_branch_name, get_git_last_commits, get_last_commit_message,
-        git_add_temporary_commit, git_undo_last_commit,
-    },
-    io::LocalPath,
+    git_repo::{get_last_commit_message, git_undo_last_commit},
 };
 use ratatui::{prelude::*, Frame};
 
 use crate::{
-    app::{AppScreen, AppState, FocusedBlock},
+    app::{ActionPreviewMode, AppScreen, AppState, FocusedBlock},
     components::{
         header::{HeaderComponent, HeaderStatus},
         mission::{
             action_preview::ActionPreviewComponent, actions::ActionsComponent,
-            context_files::ContextFilesComponent, message_input::MessageInputComponent,
-            project_info::ProjectInfoComponent,
+            context_files::ContextFilesComponent, context_reduction::ContextReductionComponent,
+            diff_split::DiffSplitComponent, message_input::MessageInputComponent,
+            project_info::ProjectInfoComponent, verification::VerificationComponent,
         },
         shortcuts::ShortcutsComponent,
+        text_input::TextInputComponent,
         AppComponent,
     },
+    config::{load_project_config, ProjectConfig},
+    conflicts::{find_conflicts, resolve_conflicts, unsupported_resolution, ConflictResolution},
+    context_commands::{command_item, git_diff_items, item_command, spawn_context_command},
+    events::{MissionEvent, VerificationEvent},
+    history::{set_history_outcome, MissionOutcome},
+    missions::{
+        apply_actions, build_mission_data, get_context_file_content, ActionType, MissionRequest,
+        MissionTask, ProposedAction, Refinement,
+    },
+    presets::{save_preset, ContextPreset, PresetScope},
+    reduction::{ContextReduction, ReducedFile, Reduction},
     shortcuts::{handle_text_input_event, ShortcutHandlerResponse},
+    utils::{
+        diff::{action_original_content, action_proposed_content, diff_hunks},
+        git::{git_add_temporary_commit_paths, git_last_commit_summaries},
+        line_ranges::{add_line_range, format_context_item, parse_context_item, LineRange},
+        list::SelectableList,
+    },
+    verification::{rollback_mission, Verification, VerificationStatus},
 };
 
-use super::{AppViewTrait, SearchableListType};
+use super::{project_picker_items, AppViewTrait, SearchableListType};
+
+// lines scrolled by PageUp and PageDown in the preview
+const PREVIEW_SCROLL_STEP: u16 = 10;
+// commits listed in the commit picker
+const COMMIT_COUNT: usize = 10;
 
 pub struct MissionView;
 
@@ -54,10 +73,44 @@ impl MissionView {
                         0 => true,
                         _ => false,
                     };
-                    return self.send_message(state, should_generate_context).await;
+                    return send_mission_message(state, should_generate_context);
                 }
                 return handle_text_input_event(state, key, &FocusedBlock::Message);
             }
+            FocusedBlock::Feedback => {
+                match key.code {
+                    KeyCode::Enter => return send_feedback(state),
+                    KeyCode::Esc => {
+                        state.set_focused_block(FocusedBlock::Actions);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    _ => {}
+                }
+                return handle_text_input_event(state, key, &FocusedBlock::Feedback);
+            }
+            FocusedBlock::PresetNameInput => {
+                match key.code {
+                    KeyCode::Enter => return save_context_preset(state, PresetScope::Personal),
+                    KeyCode::Tab => return save_context_preset(state, PresetScope::Shared),
+                    KeyCode::Esc => {
+                        state.set_focused_block(FocusedBlock::ContextFiles);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    _ => {}
+                }
+                return handle_text_input_event(state, key, &FocusedBlock::PresetNameInput);
+            }
+            FocusedBlock::ContextCommandInput => {
+                match key.code {
+                    KeyCode::Enter => return add_context_command(state),
+                    KeyCode::Esc => {
+                        state.set_focused_block(FocusedBlock::ContextFiles);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    _ => {}
+                }
+                return handle_text_input_event(state, key, &FocusedBlock::ContextCommandInput);
+            }
             FocusedBlock::ContextFiles => match key.code {
                 KeyCode::Up => {
                     state.context_items.select_previous();
@@ -72,33 +125,31 @@ impl MissionView {
                     return Ok(ShortcutHandlerResponse::StopPropagation);
                 }
                 KeyCode::Char('p') => {
-                    let project_files = state.get_project_files().unwrap_or_default();
-                    let list: Vec<(String, String)> = project_files
-                        .iter()
-                        .map(|file_path| (file_path.clone(), String::from("")))
-                        .collect();
-                    // state.searchable_list.set_items(list);
+                    let list = project_picker_items(state.get_project_files().unwrap_or_default());
                     state.set_searchable_list(list, SearchableListType::ProjectFiles);
                     state.set_screen(AppScreen::AddContextFiles);
                     state.set_focused_block(FocusedBlock::SearchContextFileInput);
                     return Ok(ShortcutHandlerResponse::StopPropagation);
                 }
                 KeyCode::Char('g') => {
-                    let gitlog = match get_git_last_commits(&state.project_dir) {
-                        Ok(gitlog) => gitlog,
+                    let commits = match git_last_commit_summaries(&state.project_dir, COMMIT_COUNT)
+                    {
+                        Ok(commits) => commits,
                         Err(err) => {
                             state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
                             return Ok(ShortcutHandlerResponse::StopPropagation);
                         }
                     };
-                    let list: Vec<(String, String)> = gitlog
-                        .iter()
-                        .map(|commit| {
+                    // the diffs come first, they are what most missions fixing changes need
+                    let list: Vec<(String, String)> = git_diff_items(state.get_base_branch_name())
+                        .into_iter()
+                        .map(|item| (item, String::from("")))
+                        .chain(commits.iter().map(|commit| {
                             (
                                 format!("{} {}", commit.hash, commit.message),
-                                String::from(""),
+                                format!("{}, {}, {}", commit.author, commit.date, commit.stat),
                             )
-                        })
+                        }))
                         .collect();
                     state.set_searchable_list(list, SearchableListType::GitCommits);
                     state.set_screen(AppScreen::AddContextFiles);
@@ -116,7 +167,12 @@ impl MissionView {
                         .items
                         .clone()
                         .iter()
-                        .map(|mission| (mission.path.to_string(), mission.content.to_string()))
+                        .map(|item| {
+                            (
+                                item.action.path.to_string(),
+                                item.action.content.clone().unwrap_or_default(),
+                            )
+                        })
                         .collect::<Vec<(String, String)>>();
                     let merged = current_context_items
                         .iter()
@@ -126,11 +182,49 @@ impl MissionView {
                     state.set_context_items(merged);
                     return Ok(ShortcutHandlerResponse::StopPropagation);
                 }
+                KeyCode::Char('s') => {
+                    if state.context_items.items.is_empty() {
+                        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+                            "The context is empty, there is nothing to save",
+                        )));
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    state.set_input_value(&FocusedBlock::PresetNameInput, "");
+                    state.set_focused_block(FocusedBlock::PresetNameInput);
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('P') => {
+                    state.open_screen(AppScreen::Presets);
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('$') => {
+                    state.set_input_value(&FocusedBlock::ContextCommandInput, "");
+                    state.set_focused_block(FocusedBlock::ContextCommandInput);
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                // command items are refreshed, there are no lines to pick in them
+                KeyCode::Char('r') if is_command_selected(state) => {
+                    if let Some((item, _)) = state.context_items.get_selected_item().cloned() {
+                        run_context_command(state, &item);
+                    }
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('r') => {
+                    if state.context_items.get_selected_item().is_some() {
+                        state.range_cursor = 1;
+                        state.range_anchor = None;
+                        state.preview_scroll = 0;
+                        state.set_focused_block(FocusedBlock::ContextRanges);
+                    }
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
                 KeyCode::Char('o') => {
                     let current_context = state.context_items.get_selected_item();
                     let file_path = match current_context {
-                        Some(file_path) => file_path.0.clone(),
-                        None => {
+                        Some(item) if item_command(&item.0).is_none() => {
+                            parse_context_item(&item.0).0.to_string()
+                        }
+                        _ => {
                             return Ok(ShortcutHandlerResponse::StopPropagation);
                         }
                     };
@@ -141,35 +235,46 @@ impl MissionView {
             FocusedBlock::Actions => match key.code {
                 KeyCode::Up => {
                     state.action_items.select_previous();
+                    state.preview_scroll = 0;
                     return Ok(ShortcutHandlerResponse::StopPropagation);
                 }
                 KeyCode::Down => {
                     state.action_items.select_next();
+                    state.preview_scroll = 0;
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::PageUp => {
+                    state.preview_scroll = state.preview_scroll.saturating_sub(PREVIEW_SCROLL_STEP);
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::PageDown => {
+                    state.preview_scroll = state.preview_scroll.saturating_add(PREVIEW_SCROLL_STEP);
                     return Ok(ShortcutHandlerResponse::StopPropagation);
                 }
                 KeyCode::Char('y') => {
-                    // approve and run
-                    match _approve_and_run(state).await {
-                        Ok(_) => {
-                            state.set_screen(AppScreen::Mission);
-                            state.set_focused_block(FocusedBlock::Home);
-                            state.set_input_value(&FocusedBlock::Message, "");
-                            state.set_current_execution_id(None);
-                            // _replace_context_files_with_actions(state);
-                            // state.set_context_items(vec![]);
-                            // state.set_action_items(vec![]);
-                            state.set_header_status(HeaderStatus::SuccessMessage(String::from(
-                                "Mission executed successfully",
-                            )));
-                            return Ok(ShortcutHandlerResponse::StopPropagation);
-                        }
-                        Err(err) => {
-                            state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
-                            return Ok(ShortcutHandlerResponse::StopPropagation);
-                        }
+                    if mission_pending(state) {
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
                     }
+                    let conflicts = find_conflicts(
+                        &state.project_dir,
+                        &state.current_snapshot(),
+                        &state.action_items.items,
+                    );
+                    if !conflicts.is_empty() {
+                        state.set_header_status(HeaderStatus::ErrorMessage(format!(
+                            "{} files changed since the actions were proposed",
+                            conflicts.len()
+                        )));
+                        state.file_conflicts = SelectableList::new(Some(0), conflicts);
+                        state.set_focused_block(FocusedBlock::Conflicts);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    return approve(state).await;
                 }
                 KeyCode::Char('x') => {
+                    if mission_pending(state) {
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
                     match state.set_execution_fail().await {
                         Ok(_) => {}
                         Err(err) => {
@@ -177,6 +282,16 @@ impl MissionView {
                             return Ok(ShortcutHandlerResponse::StopPropagation);
                         }
                     };
+                    let actions = state
+                        .action_items
+                        .items
+                        .iter()
+                        .map(|item| item.action.clone())
+                        .collect();
+                    state
+                        .record_mission(actions, MissionOutcome::Rejected, None)
+                        .ok();
+                    fail_other_revisions(state);
                     state.set_screen(AppScreen::Mission);
                     state.set_focused_block(FocusedBlock::Home);
                     state.set_input_value(&FocusedBlock::Message, "");
@@ -188,7 +303,7 @@ impl MissionView {
                 KeyCode::Char('o') => {
                     let current_action = state.action_items.get_selected_item();
                     let file_path = match current_action {
-                        Some(action) => action.path.clone(),
+                        Some(item) => item.action.path.clone(),
                         None => {
                             return Ok(ShortcutHandlerResponse::StopPropagation);
                         }
@@ -201,10 +316,82 @@ impl MissionView {
                         }
                     };
                 }
+                KeyCode::Char('v') => {
+                    state.action_preview_mode = match state.action_preview_mode {
+                        ActionPreviewMode::Content => ActionPreviewMode::Diff,
+                        _ => ActionPreviewMode::Content,
+                    };
+                    state.preview_scroll = 0;
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('s') => {
+                    state.action_preview_mode = match state.action_preview_mode {
+                        ActionPreviewMode::SideBySide => ActionPreviewMode::Diff,
+                        _ => ActionPreviewMode::SideBySide,
+                    };
+                    state.preview_scroll = 0;
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('t') => {
+                    if let Some(index) = state.action_items.selected_index {
+                        if let Some(item) = state.action_items.items.get_mut(index) {
+                            item.checked = !item.checked;
+                        }
+                    }
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('h') => {
+                    let action_type = match state.action_items.get_selected_item() {
+                        Some(item) => item.action.action_type,
+                        None => return Ok(ShortcutHandlerResponse::StopPropagation),
+                    };
+                    if action_type == ActionType::DeleteFile {
+                        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+                            "Deleted files have no hunks to review",
+                        )));
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    state.selected_hunk = 0;
+                    state.set_focused_block(FocusedBlock::ActionHunks);
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('f') => {
+                    state.set_focused_block(FocusedBlock::Feedback);
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('[') => {
+                    state.select_revision(state.current_revision.saturating_sub(1));
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char(']') => {
+                    state.select_revision(state.current_revision + 1);
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('e') => {
+                    let action_type = match state.action_items.get_selected_item() {
+                        Some(item) => item.action.action_type,
+                        None => return Ok(ShortcutHandlerResponse::StopPropagation),
+                    };
+                    if action_type == ActionType::DeleteFile {
+                        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+                            "Deleted files have no content to edit",
+                        )));
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    state.edit_action_requested = true;
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
+                KeyCode::Char('T') => {
+                    let all_checked = state.action_items.items.iter().all(|item| item.checked);
+                    for item in state.action_items.items.iter_mut() {
+                        item.checked = !all_checked;
+                    }
+                    return Ok(ShortcutHandlerResponse::StopPropagation);
+                }
                 KeyCode::Char(' ') => {
                     let current_action = state.action_items.get_selected_item();
                     let file_path = match current_action {
-                        Some(action) => action.path.clone(),
+                        Some(item) => item.action.path.clone(),
                         None => {
                             return Ok(ShortcutHandlerResponse::StopPropagation);
                         }
@@ -214,6 +401,286 @@ impl MissionView {
                 }
                 _ => {}
             },
+            FocusedBlock::Conflicts => {
+                let resolution = match key.code {
+                    KeyCode::Up => {
+                        state.file_conflicts.select_previous();
+                        None
+                    }
+                    KeyCode::Down => {
+                        state.file_conflicts.select_next();
+                        None
+                    }
+                    KeyCode::Char('o') => Some(ConflictResolution::Overwrite),
+                    KeyCode::Char('s') => Some(ConflictResolution::Skip),
+                    KeyCode::Char('m') => Some(ConflictResolution::Merge),
+                    KeyCode::Char('y') => {
+                        if mission_pending(state) {
+                            return Ok(ShortcutHandlerResponse::StopPropagation);
+                        }
+                        let snapshot = state.current_snapshot();
+                        let conflicts = state.file_conflicts.items.clone();
+                        if let Err(err) = resolve_conflicts(
+                            &state.project_dir,
+                            &snapshot,
+                            &conflicts,
+                            &mut state.action_items.items,
+                        ) {
+                            state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
+                            return Ok(ShortcutHandlerResponse::StopPropagation);
+                        }
+                        state.file_conflicts = SelectableList::new(None, vec![]);
+                        state.set_focused_block(FocusedBlock::Actions);
+                        return approve(state).await;
+                    }
+                    KeyCode::Esc => {
+                        state.file_conflicts = SelectableList::new(None, vec![]);
+                        state.set_focused_block(FocusedBlock::Actions);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    KeyCode::Char('q') => return Ok(ShortcutHandlerResponse::Continue),
+                    _ => None,
+                };
+                if let Some(resolution) = resolution {
+                    let action_items = &state.action_items.items;
+                    let unsupported =
+                        state
+                            .file_conflicts
+                            .get_selected_item()
+                            .and_then(|conflict| {
+                                unsupported_resolution(conflict, resolution, action_items)
+                            });
+                    match unsupported {
+                        Some(reason) => state
+                            .set_header_status(HeaderStatus::ErrorMessage(String::from(reason))),
+                        None => {
+                            if let Some(conflict) = state.file_conflicts.get_selected_item_mut() {
+                                conflict.resolution = resolution;
+                            }
+                        }
+                    }
+                }
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            FocusedBlock::ContextRanges => {
+                let item = match state.context_items.get_selected_item() {
+                    Some(item) => item.0.clone(),
+                    None => {
+                        state.set_focused_block(FocusedBlock::ContextFiles);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                };
+                let (path, ranges) = parse_context_item(&item);
+                let line_count = std::fs::read_to_string(format!("{}/{}", state.project_dir, path))
+                    .map(|content| content.lines().count())
+                    .unwrap_or(0)
+                    .max(1);
+                let ranges = match key.code {
+                    KeyCode::Up => {
+                        state.range_cursor = state.range_cursor.saturating_sub(1).max(1);
+                        None
+                    }
+                    KeyCode::Down => {
+                        state.range_cursor = (state.range_cursor + 1).min(line_count);
+                        None
+                    }
+                    KeyCode::PageUp => {
+                        state.range_cursor = state
+                            .range_cursor
+                            .saturating_sub(PREVIEW_SCROLL_STEP as usize)
+                            .max(1);
+                        None
+                    }
+                    KeyCode::PageDown => {
+                        state.range_cursor =
+                            (state.range_cursor + PREVIEW_SCROLL_STEP as usize).min(line_count);
+                        None
+                    }
+                    KeyCode::Char(' ') => {
+                        state.range_anchor = match state.range_anchor {
+                            Some(_) => None,
+                            None => Some(state.range_cursor),
+                        };
+                        None
+                    }
+                    KeyCode::Enter => {
+                        let anchor = state.range_anchor.take().unwrap_or(state.range_cursor);
+                        let range = LineRange::new(anchor, state.range_cursor);
+                        Some(add_line_range(&ranges, range))
+                    }
+                    KeyCode::Char('d') => Some(
+                        ranges
+                            .iter()
+                            .filter(|range| !range.contains(state.range_cursor))
+                            .copied()
+                            .collect(),
+                    ),
+                    KeyCode::Char('w') => Some(vec![]),
+                    KeyCode::Esc => {
+                        state.range_anchor = None;
+                        state.preview_scroll = 0;
+                        state.set_focused_block(FocusedBlock::ContextFiles);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    KeyCode::Char('q') => return Ok(ShortcutHandlerResponse::Continue),
+                    _ => None,
+                };
+                if let Some(ranges) = ranges {
+                    let new_item = format_context_item(path, &ranges);
+                    let already_added = state
+                        .context_items
+                        .items
+                        .iter()
+                        .any(|(other, _)| other == &new_item && other != &item);
+                    match already_added {
+                        true => state.set_header_status(HeaderStatus::ErrorMessage(format!(
+                            "{} is already in the context",
+                            new_item
+                        ))),
+                        false => {
+                            if let Some(selected) = state.context_items.get_selected_item_mut() {
+                                selected.0 = new_item;
+                            }
+                        }
+                    }
+                }
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            FocusedBlock::ContextReduction => {
+                let reduction = match state.context_reduction.as_mut() {
+                    Some(reduction) => reduction,
+                    None => {
+                        state.set_focused_block(FocusedBlock::Message);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                };
+                let new_reduction = match key.code {
+                    KeyCode::Up => {
+                        reduction.files.select_previous();
+                        None
+                    }
+                    KeyCode::Down => {
+                        reduction.files.select_next();
+                        None
+                    }
+                    KeyCode::Char('f') => Some(Reduction::Full),
+                    KeyCode::Char('c') => Some(Reduction::StripComments),
+                    KeyCode::Char('o') => Some(Reduction::Outline),
+                    KeyCode::Char('m') => Some(Reduction::Focus),
+                    KeyCode::Enter => return send_reduced_mission(state),
+                    KeyCode::Esc => {
+                        state.context_reduction = None;
+                        state.set_focused_block(FocusedBlock::Message);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    KeyCode::Char('q') => return Ok(ShortcutHandlerResponse::Continue),
+                    _ => None,
+                };
+                if let Some(new_reduction) = new_reduction {
+                    if !reduction.set_selected_reduction(new_reduction, &state.token_counter) {
+                        let reason = match new_reduction {
+                            Reduction::Focus => "The file mentions no identifier of the message",
+                            _ => "Nothing would be left of the file",
+                        };
+                        state.set_header_status(HeaderStatus::ErrorMessage(String::from(reason)));
+                    }
+                }
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            FocusedBlock::Verification => {
+                let verification = match state.verification.as_mut() {
+                    Some(verification) => verification,
+                    None => {
+                        state.set_focused_block(FocusedBlock::Home);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                };
+                match key.code {
+                    KeyCode::Up => {
+                        verification.scroll_back =
+                            (verification.scroll_back + 1).min(verification.output.len());
+                    }
+                    KeyCode::Down => {
+                        verification.scroll_back = verification.scroll_back.saturating_sub(1);
+                    }
+                    KeyCode::Char('y') | KeyCode::Enter
+                        if verification.status == VerificationStatus::Confirming =>
+                    {
+                        confirm_verification(state)
+                    }
+                    KeyCode::Char('n') | KeyCode::Esc
+                        if verification.status == VerificationStatus::Confirming =>
+                    {
+                        state.verification = None;
+                        state.set_focused_block(FocusedBlock::Home);
+                        state.set_header_status(HeaderStatus::SuccessMessage(String::from(
+                            "Verification skipped, the mission stays applied",
+                        )));
+                    }
+                    KeyCode::Char('r') => match verification.status {
+                        VerificationStatus::Failed => rollback_verified_mission(state).await,
+                        _ => state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+                            "Only a failed verification can be rolled back",
+                        ))),
+                    },
+                    KeyCode::Esc => {
+                        if verification.status == VerificationStatus::Running {
+                            verification.abort();
+                            state.set_header_status(HeaderStatus::SuccessMessage(String::from(
+                                "Verification cancelled, the mission stays applied",
+                            )));
+                        }
+                        state.verification = None;
+                        state.set_focused_block(FocusedBlock::Home);
+                    }
+                    KeyCode::Char('q') => return Ok(ShortcutHandlerResponse::Continue),
+                    _ => {}
+                }
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
+            FocusedBlock::ActionHunks => {
+                let project_dir = state.project_dir.clone();
+                let selected_hunk = state.selected_hunk;
+                let item = match state.action_items.selected_index {
+                    Some(index) => state.action_items.items.get_mut(index),
+                    None => None,
+                };
+                let item = match item {
+                    Some(item) => item,
+                    None => {
+                        state.set_focused_block(FocusedBlock::Actions);
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                };
+                let original_content = action_original_content(&project_dir, &item.action);
+                let proposed_content = action_proposed_content(&project_dir, &item.action);
+                let hunks = diff_hunks(&original_content, &proposed_content);
+                let selected_change = hunks.get(selected_hunk).map(|hunk| hunk.change.clone());
+                match (key.code, selected_change) {
+                    (KeyCode::Up, _) => {
+                        state.selected_hunk = selected_hunk.saturating_sub(1);
+                    }
+                    (KeyCode::Down, _) if selected_hunk + 1 < hunks.len() => {
+                        state.selected_hunk = selected_hunk + 1;
+                    }
+                    (KeyCode::Char('t') | KeyCode::Char(' '), Some(change)) => {
+                        item.toggle_hunk(change);
+                    }
+                    (KeyCode::Char('y'), Some(change)) => {
+                        item.rejected_hunks.remove(&change);
+                    }
+                    (KeyCode::Char('n'), Some(change)) => {
+                        item.rejected_hunks.insert(change);
+                    }
+                    (KeyCode::Esc | KeyCode::Char('h'), _) => {
+                        state.preview_scroll = 0;
+                        state.set_focused_block(FocusedBlock::Actions);
+                    }
+                    (KeyCode::Char('q'), _) => return Ok(ShortcutHandlerResponse::Continue),
+                    _ => {}
+                }
+                return Ok(ShortcutHandlerResponse::StopPropagation);
+            }
             _ => {}
         }
 
@@ -230,6 +697,10 @@ impl MissionView {
                 state.set_focused_block(FocusedBlock::Actions);
                 Ok(ShortcutHandlerResponse::StopPropagation)
             }
+            KeyCode::Char('H') => {
+                state.open_screen(AppScreen::History);
+                Ok(ShortcutHandlerResponse::StopPropagation)
+            }
             KeyCode::Char('u') => {
                 let commit_message = match get_last_commit_message(&state.project_dir) {
                     Ok(commit_message) => commit_message,
@@ -268,160 +739,678 @@ impl MissionView {
             _ => Ok(ShortcutHandlerResponse::Continue),
         }
     }
+}
 
-    async fn send_message(
-        &mut self,
-        state: &mut AppState,
-        generate_context: bool,
-    ) -> Result<ShortcutHandlerResponse> {
-        state.set_header_status(HeaderStatus::LoadingMessage(String::from(
-            "Updating previous execution...",
+/// Starts a mission with the message input, using the context files unless `generate_context`.
+pub fn send_mission_message(
+    state: &mut AppState,
+    generate_context: bool,
+) -> Result<ShortcutHandlerResponse> {
+    start_mission(state, generate_context, None)
+}
+
+// sends the feedback with the current actions, the answer becomes a new revision
+fn send_feedback(state: &mut AppState) -> Result<ShortcutHandlerResponse> {
+    let feedback = state.get_input_value_from_focused(FocusedBlock::Feedback);
+    if feedback.trim().is_empty() {
+        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+            "Feedback cannot be empty",
+        )));
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+    if state.get_current_execution_id().is_none() {
+        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+            "No actions to refine",
+        )));
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+    let previous_actions = state
+        .action_items
+        .items
+        .iter()
+        .map(|item| item.reviewed_action(&state.project_dir))
+        .collect::<Result<Vec<ProposedAction>>>();
+    let previous_actions = match previous_actions {
+        Ok(previous_actions) => previous_actions,
+        Err(err) => {
+            state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
+            return Ok(ShortcutHandlerResponse::StopPropagation);
+        }
+    };
+    let refinement = Refinement {
+        feedback,
+        previous_actions,
+    };
+    let generate_context = state.context_items.items.is_empty();
+    let response = start_mission(state, generate_context, Some(refinement))?;
+    if state.mission_task.is_some() || state.deferred_mission.is_some() {
+        state.set_input_value(&FocusedBlock::Feedback, "");
+        state.set_focused_block(FocusedBlock::Actions);
+    }
+    Ok(response)
+}
+
+fn start_mission(
+    state: &mut AppState,
+    generate_context: bool,
+    refinement: Option<Refinement>,
+) -> Result<ShortcutHandlerResponse> {
+    if state.mission_task.is_some() {
+        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+            "A mission is already running",
         )));
-        state.set_execution_fail().await.ok();
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+    let message = state.get_input_value_from_focused(FocusedBlock::Message);
+    if message.is_empty() {
+        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+            "Message cannot be empty",
+        )));
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+    // the output of the commands is part of the mission, and counts for the token budget
+    if !state.pending_context_commands.is_empty() {
+        state.deferred_mission = Some((generate_context, refinement));
         state.set_header_status(HeaderStatus::LoadingMessage(String::from(
-            "Preparing execution...",
+            "Waiting for the context commands before sending the mission...",
         )));
-        let pathinfo = code_analyst::get_path_info(&state.project_dir).unwrap_or_default();
-        let message = state.get_input_value_from_focused(FocusedBlock::Message);
-        let mission_data = MissionData {
-            project_dir: state.project_dir.clone(),
-            message: message.clone(),
-            project_files: code_analyst::project_files::get_project_files(
-                LocalPath(state.project_dir.clone()),
-                &pathinfo.code_language,
-                &pathinfo.framework,
-            ),
-            code_language: pathinfo.code_language,
-            framework: pathinfo.framework,
-        };
-        if &mission_data.message == "" {
-            state.set_header_status(HeaderStatus::ErrorMessage(String::from(
-                "Message cannot be empty",
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+    let mission_data = build_mission_data(&state.project_dir, &message);
+    // recounted, files may have been added since the header counted the list
+    state.project_files_tokens = Some(
+        state
+            .token_counter
+            .count(&mission_data.project_files.join("\n")),
+    );
+    // the context files may have been edited since the header counted them
+    state.context_tokens = None;
+    // a refinement also sends the feedback and the previous actions
+    let refinement_tokens = refinement
+        .as_ref()
+        .map(|refinement| refinement.tokens(&state.token_counter))
+        .unwrap_or(0);
+    let tokens = state.mission_tokens() + refinement_tokens;
+    if tokens > state.token_counter.limit {
+        if generate_context || state.context_items.items.is_empty() {
+            state.set_header_status(HeaderStatus::ErrorMessage(format!(
+                "The mission has {} tokens, {} allows {}",
+                tokens, state.token_counter.model, state.token_counter.limit
             )));
             return Ok(ShortcutHandlerResponse::StopPropagation);
         }
-        let res_ctx = match generate_context {
-            true => match self.generate_context_files(state, &mission_data).await {
-                Ok(res_ctx) => res_ctx,
-                Err(err) => {
-                    state.set_header_status(HeaderStatus::ErrorMessage(err.message));
-                    return Ok(ShortcutHandlerResponse::StopPropagation);
-                }
-            },
-            false => CreateMissionResponse {
-                mission_id: String::from(""), // todo: does this throws an error? should be optional?
-                context_files: state
-                    .context_items
-                    .items
+        return reduce_context(state, mission_data, refinement, refinement_tokens);
+    }
+    let (command_items, file_items): (Vec<_>, Vec<_>) = state
+        .context_items
+        .items
+        .iter()
+        .partition(|(item, _)| item_command(item).is_some());
+    let context_files = match generate_context {
+        true => None,
+        false => Some(file_items.iter().map(|(k, _)| k.clone()).collect()),
+    };
+    let inline_context_files = command_items
+        .iter()
+        .map(|(item, output)| MissionExecutionContextFile {
+            path: item.clone(),
+            content: output.clone(),
+        })
+        .collect();
+    send_mission(
+        state,
+        mission_data,
+        context_files,
+        inline_context_files,
+        refinement,
+    )
+}
+
+// shows the context files reduced to fit the token budget, the mission waits for Enter
+fn reduce_context(
+    state: &mut AppState,
+    mission_data: MissionData,
+    refinement: Option<Refinement>,
+    refinement_tokens: usize,
+) -> Result<ShortcutHandlerResponse> {
+    let counter = &state.token_counter;
+    let files = state
+        .context_items
+        .items
+        .iter()
+        .map(|(path, output)| {
+            let content = match item_command(path) {
+                Some(_) => output.clone(),
+                None => get_context_file_content(&state.project_dir, path),
+            };
+            ReducedFile::new(path, content, counter)
+        })
+        .collect();
+    let budget = counter.limit.saturating_sub(
+        counter.count(&mission_data.message)
+            + state.project_files_tokens.unwrap_or(0)
+            + refinement_tokens,
+    );
+    let reduction = ContextReduction::plan(files, budget, counter, mission_data, refinement);
+    let status = match reduction.fits() {
+        true => HeaderStatus::SuccessMessage(String::from(
+            "Context reduced to fit the token budget, Enter sends the mission",
+        )),
+        false => HeaderStatus::ErrorMessage(String::from(
+            "The context is still over the token budget, remove or reduce more files",
+        )),
+    };
+    state.context_reduction = Some(reduction);
+    state.set_focused_block(FocusedBlock::ContextReduction);
+    state.set_header_status(status);
+    Ok(ShortcutHandlerResponse::StopPropagation)
+}
+
+// sends the mission waiting in the Context Reduction block
+fn send_reduced_mission(state: &mut AppState) -> Result<ShortcutHandlerResponse> {
+    let over_budget = match &state.context_reduction {
+        Some(reduction) => reduction.tokens().saturating_sub(reduction.budget),
+        None => return Ok(ShortcutHandlerResponse::StopPropagation),
+    };
+    if over_budget > 0 {
+        state.set_header_status(HeaderStatus::ErrorMessage(format!(
+            "The context is still {} tokens over the budget",
+            over_budget
+        )));
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+    let reduction = match state.context_reduction.take() {
+        Some(reduction) => reduction,
+        None => return Ok(ShortcutHandlerResponse::StopPropagation),
+    };
+    let (context_files, inline_context_files) = reduction.context_files();
+    let is_refinement = reduction.refinement.is_some();
+    let response = send_mission(
+        state,
+        reduction.mission_data,
+        Some(context_files),
+        inline_context_files,
+        reduction.refinement,
+    )?;
+    match is_refinement {
+        true => {
+            state.set_input_value(&FocusedBlock::Feedback, "");
+            state.set_focused_block(FocusedBlock::Actions);
+        }
+        false => state.set_focused_block(FocusedBlock::Message),
+    }
+    Ok(response)
+}
+
+fn send_mission(
+    state: &mut AppState,
+    mission_data: MissionData,
+    context_files: Option<Vec<String>>,
+    inline_context_files: Vec<MissionExecutionContextFile>,
+    refinement: Option<Refinement>,
+) -> Result<ShortcutHandlerResponse> {
+    let is_refinement = refinement.is_some();
+    let request = MissionRequest {
+        mission_data,
+        context_files,
+        refinement,
+        inline_context_files,
+    };
+    let previous_execution_id = state.get_current_execution_id();
+    // a refinement leaves the current actions approvable while it runs
+    if !is_refinement {
+        state.set_current_execution_id(None);
+    }
+    state.set_header_status(HeaderStatus::Idle);
+    state.mission_task = Some(MissionTask::spawn(
+        state.backend.clone(),
+        state.event_sender.clone(),
+        request,
+        state.action_items.clone(),
+        state.context_items.clone(),
+        previous_execution_id,
+    ));
+    Ok(ShortcutHandlerResponse::StopPropagation)
+}
+
+// fails the executions of the revisions that were not chosen and forgets the revision chain
+fn fail_other_revisions(state: &mut AppState) {
+    for execution_id in state.take_other_revision_execution_ids() {
+        let backend = state.backend.clone();
+        tokio::spawn(async move {
+            backend.set_fail(SetFailRequest { execution_id }).await.ok();
+        });
+    }
+}
+
+pub fn cancel_mission(state: &mut AppState) {
+    if state.deferred_mission.take().is_some() {
+        state.set_header_status(HeaderStatus::SuccessMessage(String::from(
+            "Mission cancelled",
+        )));
+        return;
+    }
+    let mission_task = match state.mission_task.take() {
+        Some(mission_task) => mission_task,
+        None => return,
+    };
+    mission_task.abort();
+    state.action_items = mission_task.previous_action_items;
+    state.context_items = mission_task.previous_context_items;
+    state.set_current_execution_id(mission_task.previous_execution_id);
+    state.set_header_status(HeaderStatus::SuccessMessage(String::from(
+        "Mission cancelled",
+    )));
+}
+
+pub fn handle_mission_event(state: &mut AppState, task_id: usize, event: MissionEvent) {
+    let is_current_task = match &state.mission_task {
+        Some(mission_task) => mission_task.id == task_id,
+        None => false,
+    };
+    if !is_current_task {
+        // the task was cancelled, but the execution may have been created anyway
+        if let MissionEvent::Executed(res_exec, _) = event {
+            let execution_id = res_exec.execution_id.clone();
+            let backend = state.backend.clone();
+            tokio::spawn(async move {
+                backend.set_fail(SetFailRequest { execution_id }).await.ok();
+            });
+        }
+        return;
+    }
+    match event {
+        MissionEvent::Phase(phase) => {
+            if let Some(mission_task) = state.mission_task.as_mut() {
+                mission_task.set_phase(phase);
+            }
+        }
+        MissionEvent::ContextGenerated(res_ctx) => {
+            state.set_context_items(
+                res_ctx
+                    .context_files
                     .iter()
-                    .map(|(k, _)| k.clone())
-                    .collect(),
-                mission_status: CodeMissionStatus::Created,
-            },
-        };
-        let res_exec = match self.execute_mission(state, mission_data, res_ctx).await {
-            Ok(res_exec) => res_exec,
-            Err(err) => {
-                state.set_header_status(HeaderStatus::ErrorMessage(err.message));
-                return Ok(ShortcutHandlerResponse::StopPropagation);
+                    .map(|f| (f.as_str(), f.as_str()))
+                    .collect::<Vec<(&str, &str)>>(),
+            );
+        }
+        MissionEvent::Executed(res_exec, snapshot) => {
+            let (feedback, previous_execution_id) = match state.mission_task.take() {
+                Some(mission_task) => (mission_task.feedback, mission_task.previous_execution_id),
+                None => (None, None),
+            };
+            // the earlier revisions stay around while refining, a new mission replaces them
+            if feedback.is_none() {
+                fail_other_revisions(state);
+                if let Some(execution_id) = previous_execution_id {
+                    let backend = state.backend.clone();
+                    tokio::spawn(async move {
+                        backend.set_fail(SetFailRequest { execution_id }).await.ok();
+                    });
+                }
             }
-        };
-        let mission_actions = res_exec.original_actions;
-        state.set_action_items(mission_actions);
-        Ok(ShortcutHandlerResponse::StopPropagation)
+            // do not steal the focus from other screens
+            let focused_block = state.focused_block.clone();
+            state.push_revision(res_exec, feedback, snapshot);
+            if state.screen != AppScreen::Mission {
+                state.set_focused_block(focused_block);
+            }
+        }
+        MissionEvent::Failed(message) => {
+            let mission_task = state.mission_task.take();
+            state
+                .record_mission(vec![], MissionOutcome::Failed, None)
+                .ok();
+            // the previous actions are still shown, and still approvable
+            if let Some(mission_task) = mission_task {
+                state.set_current_execution_id(mission_task.previous_execution_id);
+            }
+            state.set_header_status(HeaderStatus::ErrorMessage(message));
+        }
     }
+}
 
-    async fn execute_mission(
-        &mut self,
-        state: &mut AppState,
-        mission_data: MissionData,
-        res_ctx: CreateMissionResponse,
-    ) -> Result<MissionExecution, ApiError> {
-        state.set_header_status(HeaderStatus::LoadingMessage(String::from(
-            "Executing mission...",
+// a running refinement replaces the actions when it answers, so they can not be decided yet
+fn mission_pending(state: &mut AppState) -> bool {
+    let pending = state.mission_task.is_some() || state.deferred_mission.is_some();
+    if pending {
+        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+            "Wait for the running mission, or cancel it with Ctrl+c",
         )));
-        let context_files = res_ctx
-            .context_files
-            .iter()
-            .map(|file_path| MissionExecutionContextFile {
-                path: file_path.clone(),
-                content: self.get_context_file_content(state, file_path),
-            })
-            .collect::<Vec<MissionExecutionContextFile>>();
-        let req_exec = ExecuteMissionRequest {
-            mission_id: res_ctx.mission_id.clone(),
-            mission_data,
-            context_files,
-        };
-        let res_exec = match code_missions_api::execute_mission(req_exec).await {
-            Ok(response) => {
-                state.set_header_status(HeaderStatus::Idle);
-                state.set_current_execution_id(Some(response.execution_id.clone()));
-                response
-            }
-            Err(err) => {
-                return Err(err);
+    }
+    pending
+}
+
+// applies the checked actions and goes back home, or shows why it failed
+async fn approve(state: &mut AppState) -> Result<ShortcutHandlerResponse> {
+    let execution_id = state.get_current_execution_id();
+    match _approve_and_run(state).await {
+        Ok(commit_hash) => {
+            fail_other_revisions(state);
+            state.set_screen(AppScreen::Mission);
+            state.set_focused_block(FocusedBlock::Home);
+            state.set_input_value(&FocusedBlock::Message, "");
+            state.set_current_execution_id(None);
+            // _replace_context_files_with_actions(state);
+            // state.set_context_items(vec![]);
+            // state.set_action_items(vec![]);
+            let message = match commit_hash {
+                Some(_) => "Mission executed successfully",
+                None => "Mission executed, the files already had this content",
+            };
+            state.set_header_status(HeaderStatus::SuccessMessage(String::from(message)));
+            // nothing was committed, so there is nothing to verify or roll back
+            if let (Some(execution_id), Some(commit_hash)) = (execution_id, commit_hash) {
+                start_verification(state, execution_id, commit_hash);
             }
-        };
-        state.set_header_status(HeaderStatus::Idle);
-        Ok(res_exec)
+        }
+        Err(err) => {
+            state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
+        }
     }
+    Ok(ShortcutHandlerResponse::StopPropagation)
+}
 
-    fn get_context_file_content(&mut self, state: &mut AppState, file_path: &str) -> String {
-        let project_dir = state.project_dir.clone();
-        let content = std::fs::read_to_string(format!("{}/{}", project_dir, file_path)).unwrap();
-        content
+// runs the verify commands of the project, if any, on the commit of the mission
+fn start_verification(state: &mut AppState, execution_id: String, commit_hash: String) {
+    let project_config = match load_project_config(&state.project_dir) {
+        Ok(project_config) => project_config,
+        Err(err) => {
+            state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
+            return;
+        }
+    };
+    if project_config.verify_commands.is_empty() {
+        return;
+    }
+    if let Some(verification) = state.verification.take() {
+        verification.abort();
     }
+    state.set_focused_block(FocusedBlock::Verification);
+    // the commands come from the repository, they only run once the user agreed to them
+    if !project_config.verify_commands_trusted(&state.project_dir) {
+        state.verification = Some(Verification::confirm(
+            project_config.verify_commands,
+            execution_id,
+            commit_hash,
+            project_config.rollback_on_failure,
+        ));
+        return;
+    }
+    state.verification = Some(Verification::start(
+        &state.project_dir,
+        project_config.verify_commands,
+        execution_id,
+        commit_hash,
+        project_config.rollback_on_failure,
+        state.event_sender.clone(),
+    ));
+    state.set_header_status(HeaderStatus::LoadingMessage(String::from(
+        "Verifying the mission...",
+    )));
+}
 
-    async fn generate_context_files(
-        &mut self,
-        state: &mut AppState,
-        mission_data: &MissionData,
-    ) -> Result<CreateMissionResponse, ApiError> {
-        state.set_header_status(HeaderStatus::LoadingMessage(String::from(
-            "Generating context files...",
+// runs the verify commands the user just agreed to, and remembers the answer
+fn confirm_verification(state: &mut AppState) {
+    let verification = match state.verification.take() {
+        Some(verification) => verification,
+        None => return,
+    };
+    let project_config = ProjectConfig {
+        verify_commands: verification.commands,
+        rollback_on_failure: verification.rollback_on_failure,
+    };
+    // when it cannot be saved, the user is asked again next time
+    project_config
+        .trust_verify_commands(&state.project_dir)
+        .ok();
+    state.verification = Some(Verification::start(
+        &state.project_dir,
+        project_config.verify_commands,
+        verification.execution_id,
+        verification.commit_hash,
+        project_config.rollback_on_failure,
+        state.event_sender.clone(),
+    ));
+    state.set_header_status(HeaderStatus::LoadingMessage(String::from(
+        "Verifying the mission...",
+    )));
+}
+
+// saves the context items under the name typed in the Preset Name block
+fn save_context_preset(
+    state: &mut AppState,
+    scope: PresetScope,
+) -> Result<ShortcutHandlerResponse> {
+    let name = state.get_input_value_from_focused(FocusedBlock::PresetNameInput);
+    if name.trim().is_empty() {
+        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+            "Preset name cannot be empty",
         )));
-        let req_ctx = CreateMissionRequest {
-            mission_data: mission_data.clone(),
-        };
-        let res_ctx = match code_missions_api::create_mission(req_ctx).await {
-            Ok(response) => {
-                state.set_header_status(HeaderStatus::Idle);
-                state.set_context_items(
-                    response
-                        .context_files
-                        .clone()
-                        .iter()
-                        .map(|f| (f.as_str(), f.as_str()))
-                        .collect::<Vec<(&str, &str)>>(),
-                );
-                response
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+    let items = state
+        .context_items
+        .items
+        .iter()
+        .map(|(item, _)| item.clone())
+        .collect();
+    let preset = ContextPreset::new(&name, items, scope);
+    let status = match save_preset(&state.project_dir, preset) {
+        Ok(_) => HeaderStatus::SuccessMessage(format!(
+            "Context saved as the {} preset \"{}\"",
+            scope,
+            name.trim()
+        )),
+        Err(err) => HeaderStatus::ErrorMessage(format!("Failed to save the preset: {}", err)),
+    };
+    state.set_header_status(status);
+    state.set_input_value(&FocusedBlock::PresetNameInput, "");
+    state.set_focused_block(FocusedBlock::ContextFiles);
+    Ok(ShortcutHandlerResponse::StopPropagation)
+}
+
+fn is_command_selected(state: &AppState) -> bool {
+    match state.context_items.get_selected_item() {
+        Some((item, _)) => item_command(item).is_some(),
+        None => false,
+    }
+}
+
+// adds the command typed in the Context Command block to the context and runs it
+fn add_context_command(state: &mut AppState) -> Result<ShortcutHandlerResponse> {
+    let command = state.get_input_value_from_focused(FocusedBlock::ContextCommandInput);
+    if command.trim().is_empty() {
+        state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+            "Command cannot be empty",
+        )));
+        return Ok(ShortcutHandlerResponse::StopPropagation);
+    }
+    let item = command_item(&command);
+    if !state.context_items.items.iter().any(|(k, _)| k == &item) {
+        state.context_items.add_item((item.clone(), String::new()));
+    }
+    run_context_command(state, &item);
+    state.set_input_value(&FocusedBlock::ContextCommandInput, "");
+    state.set_focused_block(FocusedBlock::ContextFiles);
+    Ok(ShortcutHandlerResponse::StopPropagation)
+}
+
+/// Runs the command of a context item, its output replaces the one of the item when done.
+pub fn run_context_command(state: &mut AppState, item: &str) {
+    if let Some(command) = item_command(item) {
+        state.set_header_status(HeaderStatus::LoadingMessage(format!(
+            "Running `{}`...",
+            command
+        )));
+    }
+    state.pending_context_commands.insert(item.to_string());
+    spawn_context_command(&state.project_dir, item, state.event_sender.clone());
+}
+
+pub fn handle_context_command_event(state: &mut AppState, item: String, output: String) {
+    state.pending_context_commands.remove(&item);
+    // the item may have been removed while the command was running
+    if let Some(context_item) = state
+        .context_items
+        .items
+        .iter_mut()
+        .find(|(k, _)| k == &item)
+    {
+        let lines = output.lines().count();
+        context_item.1 = output;
+        if let Some(command) = item_command(&item) {
+            state.set_header_status(HeaderStatus::SuccessMessage(format!(
+                "Output of `{}` added to the context ({} lines)",
+                command, lines
+            )));
+        }
+    }
+    if !state.pending_context_commands.is_empty() {
+        return;
+    }
+    if let Some((generate_context, refinement)) = state.deferred_mission.take() {
+        let is_refinement = refinement.is_some();
+        start_mission(state, generate_context, refinement).ok();
+        if is_refinement && state.mission_task.is_some() {
+            state.set_input_value(&FocusedBlock::Feedback, "");
+            state.set_focused_block(FocusedBlock::Actions);
+        }
+    }
+}
+
+pub async fn handle_verification_event(state: &mut AppState, event: VerificationEvent) {
+    let verification = match state.verification.as_mut() {
+        // events sent before the verification was cancelled
+        Some(verification) if verification.status == VerificationStatus::Running => verification,
+        _ => return,
+    };
+    match event {
+        VerificationEvent::Output(line) => verification.output.push(line),
+        VerificationEvent::Finished(true) => {
+            verification.status = VerificationStatus::Passed;
+            set_history_outcome(
+                &state.project_dir,
+                &verification.commit_hash,
+                MissionOutcome::Verified,
+            )
+            .ok();
+            state.set_header_status(HeaderStatus::SuccessMessage(String::from(
+                "Mission verified",
+            )));
+        }
+        VerificationEvent::Finished(false) => {
+            verification.status = VerificationStatus::Failed;
+            set_history_outcome(
+                &state.project_dir,
+                &verification.commit_hash,
+                MissionOutcome::Broken,
+            )
+            .ok();
+            match verification.rollback_on_failure {
+                true => rollback_verified_mission(state).await,
+                false => state.set_header_status(HeaderStatus::ErrorMessage(String::from(
+                    "Verification failed, press r to roll the mission back",
+                ))),
             }
-            Err(err) => return Err(err),
-        };
-        Ok(res_ctx)
+        }
     }
 }
 
-async fn _approve_and_run(state: &mut AppState) -> Result<()> {
+// undoes the commit of the mission that failed verification
+async fn rollback_verified_mission(state: &mut AppState) {
+    let (execution_id, commit_hash) = match &state.verification {
+        Some(verification) if verification.status == VerificationStatus::Failed => (
+            verification.execution_id.clone(),
+            verification.commit_hash.clone(),
+        ),
+        _ => return,
+    };
+    let result = rollback_mission(
+        state.backend.as_ref(),
+        &state.project_dir,
+        &execution_id,
+        &commit_hash,
+    )
+    .await;
+    match result {
+        Ok(_) => {
+            if let Some(verification) = state.verification.as_mut() {
+                verification.status = VerificationStatus::RolledBack;
+            }
+            set_history_outcome(&state.project_dir, &commit_hash, MissionOutcome::RolledBack).ok();
+            state.set_header_status(HeaderStatus::SuccessMessage(String::from(
+                "Verification failed, the mission was rolled back",
+            )));
+        }
+        Err(err) => {
+            state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()));
+        }
+    }
+}
+
+// returns the hash of the commit, None when the actions left the files unchanged
+async fn _approve_and_run(state: &mut AppState) -> Result<Option<String>> {
     let execution_id = match state.get_current_execution_id() {
         Some(execution_id) => execution_id,
         None => {
             anyhow::bail!("No execution id found");
         }
     };
+    let checked_actions: Vec<ProposedAction> = state
+        .action_items
+        .items
+        .iter()
+        .filter(|item| item.checked)
+        .map(|item| item.reviewed_action(&state.project_dir))
+        .collect::<Result<Vec<ProposedAction>>>()?;
+    if checked_actions.is_empty() {
+        anyhow::bail!("No actions selected");
+    }
+    let has_rejected_hunks = state
+        .action_items
+        .items
+        .iter()
+        .any(|item| item.checked && !item.rejected_hunks.is_empty());
+    if checked_actions.len() < state.action_items.len() || has_rejected_hunks {
+        let req_review = ReviewActionsRequest {
+            execution_id: execution_id.clone(),
+            // lilicore has no delete and rename actions, so only the other ones are reported
+            reviewed_actions: checked_actions
+                .iter()
+                .filter_map(|action| action.to_mission_action())
+                .collect(),
+            context_files: None,
+        };
+        if let Err(err) = state.backend.review_actions(req_review).await {
+            anyhow::bail!(err.message);
+        }
+    }
     let req_approved = SetApprovedRequest {
         execution_id: execution_id.clone(),
     };
-    match set_approved(req_approved).await {
+    match state.backend.set_approved(req_approved).await {
         Ok(_) => {}
         Err(err) => {
             anyhow::bail!(err.message);
         }
     };
-    coder::run_actions(&state.project_dir, &state.action_items.items.as_ref())?;
-    git_add_temporary_commit(&state.project_dir, Some(execution_id.clone()))?;
-    Ok(())
+    apply_actions(&state.project_dir, &checked_actions)?;
+    let paths: Vec<String> = checked_actions
+        .iter()
+        .flat_map(|action| action.paths())
+        .collect();
+    let commit_hash =
+        git_add_temporary_commit_paths(&state.project_dir, &paths, Some(execution_id.clone()))?;
+    // the actions are applied already, a history that can not be written is not worth an error
+    state
+        .record_mission(
+            checked_actions,
+            MissionOutcome::Applied,
+            commit_hash.clone(),
+        )
+        .ok();
+    Ok(commit_hash)
 }
 
 impl AppViewTrait for MissionView {
@@ -434,7 +1423,33 @@ impl AppViewTrait for MissionView {
         let el_action_preview = ActionPreviewComponent::new()?;
 
         let mut components = HashMap::new();
-        components.insert(String::from("message"), el_message.as_mutex());
+        // the feedback of a refinement is typed where the message was
+        match state.focused_block {
+            FocusedBlock::Feedback => {
+                let el_feedback = TextInputComponent::new(
+                    "Feedback on the actions (Enter to refine)",
+                    FocusedBlock::Feedback,
+                )?;
+                components.insert(String::from("message"), el_feedback.as_mutex());
+            }
+            FocusedBlock::PresetNameInput => {
+                let el_preset_name = TextInputComponent::new(
+                    "Preset name (Enter to save, Tab to share with the team)",
+                    FocusedBlock::PresetNameInput,
+                )?;
+                components.insert(String::from("message"), el_preset_name.as_mutex());
+            }
+            FocusedBlock::ContextCommandInput => {
+                let el_command = TextInputComponent::new(
+                    "Command to add the output of (Enter to run)",
+                    FocusedBlock::ContextCommandInput,
+                )?;
+                components.insert(String::from("message"), el_command.as_mutex());
+            }
+            _ => {
+                components.insert(String::from("message"), el_message.as_mutex());
+            }
+        }
         components.insert(String::from("header"), el_header.as_mutex());
         components.insert(String::from("context_files"), el_context_files.as_mutex());
         components.insert(String::from("actions"), el_actions.as_mutex());
@@ -443,9 +1458,28 @@ impl AppViewTrait for MissionView {
         // all components below should be rendered to the same position
         let content_position = "project_info";
         match state.focused_block {
-            FocusedBlock::Actions | FocusedBlock::ContextFiles => {
+            FocusedBlock::Actions if state.action_preview_mode == ActionPreviewMode::SideBySide => {
+                let el_diff_split = DiffSplitComponent::new()?;
+                components.insert(String::from(content_position), el_diff_split.as_mutex());
+            }
+            FocusedBlock::Actions
+            | FocusedBlock::ActionHunks
+            | FocusedBlock::ContextFiles
+            | FocusedBlock::ContextRanges
+            | FocusedBlock::Conflicts => {
                 components.insert(String::from(content_position), el_action_preview.as_mutex());
             }
+            FocusedBlock::ContextReduction => {
+                let el_context_reduction = ContextReductionComponent::new()?;
+                components.insert(
+                    String::from(content_position),
+                    el_context_reduction.as_mutex(),
+                );
+            }
+            FocusedBlock::Verification => {
+                let el_verification = VerificationComponent::new()?;
+                components.insert(String::from(content_position), el_verification.as_mutex());
+            }
             _ => {
                 let el_project_info = ProjectInfoComponent::new(state.project_dir.clone())?;
                 components.insert(String::from(content_position), el_project_info.as_mutex());
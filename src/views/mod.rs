@@ -10,13 +10,17 @@ use std::{any::Any, collections::HashMap, rc::Rc, sync::Mutex};
 mod add_context_files;
 mod commit_temp_branch;
 mod create_temp_branch;
+mod history;
 mod mission;
+mod presets;
 mod sign_in;
 
 pub use add_context_files::*;
 pub use commit_temp_branch::*;
 pub use create_temp_branch::*;
+pub use history::*;
 pub use mission::*;
+pub use presets::*;
 pub use sign_in::*;
 
 pub trait AppViewTrait {
@@ -34,6 +38,8 @@ pub enum AppView {
     CreateTempBranch(CreateTempBranchView),
     CommitTempBranch(CommitTempBranchView),
     AddContextFiles(AddContextFilesView),
+    History(HistoryView),
+    Presets(PresetsView),
 }
 
 impl AppView {
@@ -44,6 +50,8 @@ impl AppView {
             AppView::CreateTempBranch(view) => view.components(state),
             AppView::CommitTempBranch(view) => view.components(state),
             AppView::AddContextFiles(view) => view.components(state),
+            AppView::History(view) => view.components(state),
+            AppView::Presets(view) => view.components(state),
         }?;
 
         let positions = match self {
@@ -52,6 +60,8 @@ impl AppView {
             AppView::CreateTempBranch(view) => view.positions(frame, state),
             AppView::CommitTempBranch(view) => view.positions(frame, state),
             AppView::AddContextFiles(view) => view.positions(frame, state),
+            AppView::History(view) => view.positions(frame, state),
+            AppView::Presets(view) => view.positions(frame, state),
         }?;
 
         for (name, component) in components {
@@ -79,6 +89,8 @@ impl AppView {
             AppView::CreateTempBranch(view) => view.handle_events(state, &key).await,
             AppView::CommitTempBranch(view) => view.handle_events(state, &key).await,
             AppView::AddContextFiles(view) => view.handle_events(state, &key).await,
+            AppView::History(view) => view.handle_events(state, key).await,
+            AppView::Presets(view) => view.handle_events(state, key).await,
         };
     }
 }
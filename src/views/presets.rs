@@ -0,0 +1,218 @@
+use std::{collections::HashMap, path::Path, sync::Mutex};
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::{Backend, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::{
+    app::{AppScreen, AppState, FocusedBlock},
+    components::{
+        header::{HeaderComponent, HeaderStatus},
+        presets::{preset_details::PresetDetailsComponent, preset_list::PresetListComponent},
+        shortcuts::ShortcutsComponent,
+        AppComponent,
+    },
+    context_commands::item_command,
+    presets::{delete_preset, ContextPreset, PresetScope},
+    shortcuts::ShortcutHandlerResponse,
+    utils::line_ranges::parse_context_item,
+};
+
+use super::{run_context_command, AppViewTrait};
+
+pub struct PresetsView {
+    // a shared preset with commands, waiting for the user to confirm they run, and if it merges
+    pending_load: Option<(ContextPreset, bool)>,
+}
+
+impl PresetsView {
+    pub fn new() -> Self {
+        Self { pending_load: None }
+    }
+
+    pub async fn handle_events(
+        &mut self,
+        state: &mut AppState,
+        key: &KeyEvent,
+    ) -> Result<ShortcutHandlerResponse> {
+        if let Some((preset, merge)) = self.pending_load.take() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    load_preset(state, &preset, merge);
+                    state.set_screen(AppScreen::Mission);
+                    state.set_focused_block(FocusedBlock::ContextFiles);
+                }
+                KeyCode::Char('n') | KeyCode::Esc => {}
+                _ => self.pending_load = Some((preset, merge)),
+            }
+            return Ok(ShortcutHandlerResponse::StopPropagation);
+        }
+        match key.code {
+            KeyCode::Up => state.presets.select_previous(),
+            KeyCode::Down => state.presets.select_next(),
+            KeyCode::Esc => {
+                state.set_screen(AppScreen::Mission);
+                state.set_focused_block(FocusedBlock::ContextFiles);
+            }
+            KeyCode::Enter | KeyCode::Char('m') => {
+                if let Some(preset) = state.presets.get_selected_item().cloned() {
+                    let merge = key.code == KeyCode::Char('m');
+                    // shared presets come from the repository, their commands are not run unseen
+                    if preset.scope == PresetScope::Shared && !preset_commands(&preset).is_empty() {
+                        self.pending_load = Some((preset, merge));
+                        return Ok(ShortcutHandlerResponse::StopPropagation);
+                    }
+                    load_preset(state, &preset, merge);
+                    state.set_screen(AppScreen::Mission);
+                    state.set_focused_block(FocusedBlock::ContextFiles);
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(preset) = state.presets.get_selected_item().cloned() {
+                    match delete_preset(&state.project_dir, &preset) {
+                        Ok(_) => {
+                            state.presets.remove_selected_item();
+                            state.set_header_status(HeaderStatus::SuccessMessage(format!(
+                                "Preset \"{}\" deleted",
+                                preset.name
+                            )));
+                        }
+                        Err(err) => {
+                            state.set_header_status(HeaderStatus::ErrorMessage(err.to_string()))
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(ShortcutHandlerResponse::StopPropagation)
+    }
+}
+
+fn preset_commands(preset: &ContextPreset) -> Vec<&str> {
+    preset
+        .items
+        .iter()
+        .filter_map(|item| item_command(item))
+        .collect()
+}
+
+// puts the items of the preset in the context, after the current ones when merging
+fn load_preset(state: &mut AppState, preset: &ContextPreset, merge: bool) {
+    if !merge {
+        state.set_context_items(vec![]);
+    }
+    let mut missing = 0;
+    for item in preset.items.iter() {
+        if state.context_items.items.iter().any(|(k, _)| k == item) {
+            continue;
+        }
+        if item_command(item).is_some() {
+            state.context_items.add_item((item.clone(), String::new()));
+            run_context_command(state, item);
+            continue;
+        }
+        // files removed since the preset was saved are left out
+        let (path, _) = parse_context_item(item);
+        match Path::new(&state.project_dir).join(path).exists() {
+            true => state.context_items.add_item((item.clone(), String::new())),
+            false => missing += 1,
+        }
+    }
+    let verb = match merge {
+        true => "merged",
+        false => "loaded",
+    };
+    let status = match missing {
+        0 => HeaderStatus::SuccessMessage(format!("Preset \"{}\" {}", preset.name, verb)),
+        _ => HeaderStatus::ErrorMessage(format!(
+            "Preset \"{}\" {}, {} missing files left out",
+            preset.name, verb, missing
+        )),
+    };
+    state.set_header_status(status);
+}
+
+impl AppViewTrait for PresetsView {
+    fn components(
+        &mut self,
+        _state: &mut AppState,
+    ) -> Result<HashMap<String, Mutex<AppComponent>>> {
+        let el_header = HeaderComponent::new()?;
+        let el_shortcuts = ShortcutsComponent::new()?;
+        let el_list = PresetListComponent::new()?;
+        let el_details = PresetDetailsComponent::new()?;
+
+        let mut components = HashMap::new();
+        components.insert(String::from("header"), el_header.as_mutex());
+        components.insert(String::from("shortcuts"), el_shortcuts.as_mutex());
+        components.insert(String::from("list"), el_list.as_mutex());
+        components.insert(String::from("details"), el_details.as_mutex());
+
+        Ok(components)
+    }
+
+    fn positions<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        _state: &mut AppState,
+    ) -> Result<HashMap<String, Rect>> {
+        let [top_rect, main_rect, bottom_rect] = *Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(frame.size())
+        else {
+            return Ok(HashMap::new());
+        };
+
+        let [list_rect, details_rect] = *Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+            .split(main_rect)
+        else {
+            return Ok(HashMap::new());
+        };
+
+        let mut positions = vec![
+            (String::from("header"), top_rect),
+            (String::from("shortcuts"), bottom_rect),
+            (String::from("list"), list_rect),
+        ];
+
+        // the confirmation takes the place of the details, components are drawn after this
+        match &self.pending_load {
+            Some((preset, _)) => {
+                let mut lines = vec![Line::from(String::from(
+                    "This shared preset runs these commands:",
+                ))];
+                lines.extend(
+                    preset_commands(preset)
+                        .iter()
+                        .map(|command| Line::from(format!("  $ {}", command))),
+                );
+                lines.push(Line::from(""));
+                lines.push(Line::from(String::from("Run them? (y/n)")));
+                let confirm = Paragraph::new(lines).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Run commands")
+                        .border_style(Style::default().fg(Color::Yellow)),
+                );
+                frame.render_widget(confirm, details_rect);
+            }
+            None => positions.push((String::from("details"), details_rect)),
+        }
+
+        Ok(positions.into_iter().collect())
+    }
+}
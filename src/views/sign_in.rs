@@ -2,7 +2,7 @@ use std::{collections::HashMap, rc::Rc, sync::Mutex};
 
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent};
-use lilicore::auth::{auth_login, AuthLoginRequest};
+use lilicore::auth::AuthLoginRequest;
 use ratatui::{prelude::*, Frame};
 
 use crate::{
@@ -14,7 +14,6 @@ use crate::{
         text_input::TextInputComponent,
         AppComponent,
     },
-    redraw_app,
     shortcuts::{handle_text_input_event, ShortcutHandlerResponse},
 };
 
@@ -46,29 +45,15 @@ impl SignInView {
             return Ok(ShortcutHandlerResponse::StopPropagation);
         }
         let request = AuthLoginRequest { username, password };
-        let response = match auth_login(request).await {
-            Ok(response) => response,
+        let name = match state.backend.sign_in(request).await {
+            Ok(name) => name,
             Err(err) => {
-                state.set_header_status(HeaderStatus::ErrorMessage(format!(
-                    "{:?}",
-                    err.error_description
-                )));
+                state.set_header_status(HeaderStatus::ErrorMessage(err));
                 return Ok(ShortcutHandlerResponse::StopPropagation);
             }
         };
-        // state.set_header_status(HeaderStatus::ErrorMessage(String::from(
-        //     "error message goes here",
-        // )));
         state.signed_in = true;
         state.set_header_status(HeaderStatus::Idle);
-        let decoded = match response.decode_access_token() {
-            Ok(decoded) => decoded,
-            Err(err) => {
-                state.set_header_status(HeaderStatus::ErrorMessage(format!("{:?}", err)));
-                return Ok(ShortcutHandlerResponse::StopPropagation);
-            }
-        };
-        let name = decoded.given_name;
         state.set_user_name(name);
         state.set_screen(AppScreen::Mission);
         state.set_focused_block(FocusedBlock::Home);